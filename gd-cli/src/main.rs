@@ -1,48 +1,124 @@
 #![warn(clippy::all)]
 
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs::File;
+use std::io::BufReader;
 use std::path::Path;
+use std::process;
 
 use csv::Writer;
 use docopt::Docopt;
 use gd_world::logger;
 use gd_world::prelude::*;
-use gd_world::provider::Stats as PStats;
 use gd_world::requestor::DefenceMechanismType;
-use gd_world::requestor::Stats as RStats;
-use rand::prelude::*;
-use rand_chacha::ChaChaRng;
-use rayon::prelude::*;
-use serde_derive::Deserialize;
-
-mod params;
-
-use crate::params::*;
+use gd_world::trace;
+use serde_derive::{Deserialize, Serialize};
 
 const USAGE: &str = "
 Golem marketplace agent-based DES simulator
 
 Usage:
-    golem_des <json> [--defence=<defence>] [--repetitions=<repetitions>] [--output-dir=<output-dir>] [--verbose]
+    golem_des replay <json> <trace> [--defence=<defence>] [--repetition=<repetition>] [--verbose]
+    golem_des <json> [--defence=<defence>] [--repetitions=<repetitions>] [--output-dir=<output-dir>] [--checkpoint-every=<interval>] [--resume] [--sample-every=<interval>] [--trace] [--profile] [--verbose]
     golem_des (-h | --help)
 
 Options:
     json                            JSON file with simulation parameters.
+    replay                          Re-run a repetition and compare its trace with <trace>.
+    trace                           Trace saved with --trace.
     -v --verbose                    Show debug logs.
     -h --help                       Show this screen.
     --defence=<defence>             Defence mechanism (ctasks, lgrola, or redundancy) [default: redundancy].
     --repetitions=<repetitions>     Number of repetitions [default: 100].
+    --repetition=<repetition>       Repetition to replay [default: 0].
     --output-dir=<output-dir>       Output directory for statistics.
+    --checkpoint-every=<interval>   Save the state of each repetition every <interval> simulated seconds.
+    --resume                        Resume repetitions from their last saved checkpoints.
+    --sample-every=<interval>       Save the market state of each repetition every <interval> simulated seconds.
+    --trace                         Save the market interactions of each repetition as JSON lines.
+    --profile                       Collect performance counters of the simulation engine.
 ";
 
 #[derive(Debug, Deserialize)]
 struct Args {
+    cmd_replay: bool,
     arg_json: String,
+    arg_trace: Option<String>,
     flag_defence: DefenceMechanismType,
     flag_repetitions: usize,
+    flag_repetition: u64,
     flag_verbose: bool,
     flag_output_dir: Option<String>,
+    flag_checkpoint_every: Option<f64>,
+    flag_resume: bool,
+    flag_sample_every: Option<f64>,
+    flag_trace: bool,
+    flag_profile: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ProfileRecord {
+    run_id: u64,
+    event_kind: &'static str,
+    num_events: u64,
+    wall_clock: f64,
+    queue_high_water_mark: usize,
+}
+
+fn print_profile(profiles: &[(u64, Profile)]) {
+    let mut kinds: BTreeMap<&'static str, (u64, f64)> = BTreeMap::new();
+    for (_, profile) in profiles {
+        for (&kind, stats) in &profile.kinds {
+            let entry = kinds.entry(kind).or_default();
+            entry.0 += stats.count;
+            entry.1 += stats.wall_clock.as_secs_f64();
+        }
+    }
+
+    println!(
+        "{:<28}{:>14}{:>18}{:>18}",
+        "event kind", "events", "wall clock [s]", "per event [us]"
+    );
+    for (kind, (count, wall_clock)) in kinds {
+        println!(
+            "{:<28}{:>14}{:>18.3}{:>18.3}",
+            kind,
+            count,
+            wall_clock,
+            wall_clock * 1e6 / count as f64
+        );
+    }
+
+    let high_water_mark = profiles
+        .iter()
+        .map(|(_, profile)| profile.queue_high_water_mark)
+        .max()
+        .unwrap_or(0);
+    println!("queue high-water mark: {}", high_water_mark);
+}
+
+fn replay(params: SimulationParams, args: &Args) -> Result<(), Box<dyn Error>> {
+    if params.seed.is_none() {
+        return Err("only scenarios with a fixed seed can be replayed".into());
+    }
+
+    let path = args.arg_trace.as_ref().expect("trace not found");
+    let expected = trace::read_trace(BufReader::new(File::open(path)?))?;
+
+    let mut experiment = Experiment::new(params, args.flag_repetition as usize + 1);
+    experiment.set_defence_mechanism(args.flag_defence);
+
+    match experiment.replay(args.flag_repetition, &expected) {
+        None => {
+            println!("traces match ({} events)", expected.len());
+            Ok(())
+        }
+        Some(divergence) => {
+            println!("{}", divergence);
+            process::exit(1);
+        }
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -59,83 +135,103 @@ fn main() -> Result<(), Box<dyn Error>> {
     let file = File::open(Path::new(&args.arg_json))?;
     let params: SimulationParams = serde_json::from_reader(file)?;
 
-    let results: Vec<(Vec<RStats>, Vec<PStats>)> = (0..args.flag_repetitions)
-        .into_par_iter()
-        .map(|run_num| {
-            let mut rng = match params.seed {
-                Some(seed) => ChaChaRng::seed_from_u64(seed + run_num as u64),
-                None => ChaChaRng::from_entropy(),
-            };
-
-            // create pre-specified actors
-            let mut requestors = Vec::new();
-            if let Some(rs) = &params.requestors {
-                for spec in rs {
-                    requestors.push(spec.as_requestor(&mut rng, args.flag_defence));
-                }
-            }
+    if args.cmd_replay {
+        return replay(params, &args);
+    }
 
-            let mut providers = Vec::new();
-            if let Some(ps) = &params.providers {
-                for spec in ps {
-                    providers.push(spec.as_provider());
-                }
-            }
+    let output_dir = Path::new(match &args.flag_output_dir {
+        None => ".",
+        Some(path) => path,
+    });
 
-            // create random actors
-            if let Some(sources) = &params.requestor_sources {
-                for source in sources {
-                    for requestor in source.iter(&mut rng, args.flag_defence) {
-                        requestors.push(requestor);
-                    }
-                }
-            }
+    let create_path = |fname: &str, id: Option<u64>, ext: &str| {
+        let name =
+            String::from(fname) + "_" + &id.map(|value| value.to_string()).unwrap_or_default();
+        output_dir.join(name).with_extension(ext)
+    };
 
-            if let Some(sources) = &params.provider_sources {
-                for source in sources {
-                    for provider in source.iter(&mut rng) {
-                        providers.push(provider);
-                    }
-                }
-            }
+    let seed = params.seed;
 
-            // create the simulation world; aka the marketplace
-            let mut world = World::new(rng);
+    let mut experiment = Experiment::new(params, args.flag_repetitions);
+    experiment.set_defence_mechanism(args.flag_defence);
 
-            // append actors
-            world.append_requestors(requestors);
-            world.append_providers(providers);
+    if let Some(interval) = args.flag_checkpoint_every {
+        experiment.enable_checkpoints(interval, output_dir);
+    }
 
-            // run the simulation
-            world.run(params.duration);
+    if args.flag_resume {
+        experiment.resume_from(output_dir);
+    }
 
-            // gather statistics
-            world.into_stats(run_num as u64)
-        })
-        .collect();
+    if args.flag_profile {
+        experiment.enable_profiling();
+    }
 
-    let create_path = |fname: &str, id: Option<u64>| {
-        let path = Path::new(match &args.flag_output_dir {
-            None => ".",
-            Some(path) => &path,
-        });
+    if let Some(interval) = args.flag_sample_every {
+        experiment.enable_sampling(interval);
+    }
 
-        let name =
-            String::from(fname) + "_" + &id.map(|value| value.to_string()).unwrap_or_default();
-        path.join(name).with_extension("csv")
-    };
+    if args.flag_trace {
+        experiment.enable_tracing(output_dir);
+    }
+
+    let results = experiment.run();
+
+    let mut requestors_wtr = Writer::from_path(create_path("requestors_stats", seed, "csv"))?;
+    let mut providers_wtr = Writer::from_path(create_path("providers_stats", seed, "csv"))?;
+
+    let mut profiles = Vec::new();
 
-    let mut requestors_wtr = Writer::from_path(create_path("requestors_stats", params.seed))?;
-    let mut providers_wtr = Writer::from_path(create_path("providers_stats", params.seed))?;
+    let has_stop = experiment.params().stop.is_some();
 
-    for (requestors, providers) in results {
-        for requestor in requestors {
+    for result in results {
+        if has_stop {
+            println!(
+                "repetition {} ended at {:.0}s ({})",
+                result.run_id,
+                result.run_length,
+                result.stop_reason.as_str()
+            );
+        }
+
+        for requestor in result.requestors {
             requestors_wtr.serialize(requestor)?;
         }
 
-        for provider in providers {
+        for provider in result.providers {
             providers_wtr.serialize(provider)?;
         }
+
+        if args.flag_sample_every.is_some() {
+            let fname = format!("timeseries_{}", result.run_id);
+            let mut samples_wtr = Writer::from_path(create_path(&fname, seed, "csv"))?;
+
+            for sample in result.samples {
+                samples_wtr.serialize(sample)?;
+            }
+        }
+
+        if let Some(profile) = result.profile {
+            profiles.push((result.run_id, profile));
+        }
+    }
+
+    if args.flag_profile {
+        let mut profile_wtr = Writer::from_path(create_path("profile", seed, "csv"))?;
+
+        for (run_id, profile) in &profiles {
+            for (&event_kind, stats) in &profile.kinds {
+                profile_wtr.serialize(ProfileRecord {
+                    run_id: *run_id,
+                    event_kind,
+                    num_events: stats.count,
+                    wall_clock: stats.wall_clock.as_secs_f64(),
+                    queue_high_water_mark: profile.queue_high_water_mark,
+                })?;
+            }
+        }
+
+        print_profile(&profiles);
     }
 
     Ok(())
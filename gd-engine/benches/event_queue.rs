@@ -0,0 +1,88 @@
+// Hold model benchmark of the event queue backends: the queue is first filled
+// with N events, after which every dispatched event schedules a new one. Run
+// with `cargo bench -p gd-engine`.
+use std::time::{Duration, Instant};
+
+use gd_engine::{Engine, QueueKind};
+use rand::distributions::{Distribution, Exp, Uniform};
+use rand::prelude::*;
+use rand_chacha::ChaChaRng;
+
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+const HOLDS: usize = 1_000_000;
+const KINDS: [QueueKind; 3] = [
+    QueueKind::BinaryHeap,
+    QueueKind::Calendar,
+    QueueKind::Ladder,
+];
+
+#[derive(Clone, Copy, Debug)]
+enum Delay {
+    Exponential,
+    Uniform,
+    // mimics the task readvertisements of the simulation: most events fall
+    // on a handful of fixed offsets
+    Readvert,
+}
+
+impl Delay {
+    fn sample<R: Rng>(self, rng: &mut R) -> f64 {
+        match self {
+            Delay::Exponential => Exp::new(1.0).sample(rng),
+            Delay::Uniform => Uniform::new(0.0, 2.0).sample(rng),
+            Delay::Readvert => {
+                if rng.gen_bool(0.9) {
+                    *[60.0, 120.0, 3600.0].choose(rng).expect("no delay")
+                } else {
+                    Exp::new(0.1).sample(rng)
+                }
+            }
+        }
+    }
+}
+
+fn hold(kind: QueueKind, delay: Delay, size: usize) -> Duration {
+    let mut rng = ChaChaRng::seed_from_u64(0);
+    let mut engine = Engine::with_queue(kind);
+
+    for i in 0..size {
+        engine.schedule(delay.sample(&mut rng), i);
+    }
+
+    let start = Instant::now();
+    for _ in 0..HOLDS {
+        let event = engine.pop().expect("queue is empty");
+        engine.schedule(delay.sample(&mut rng), event);
+    }
+
+    start.elapsed()
+}
+
+fn main() {
+    println!(
+        "{:<12}{:>10}{:>16}{:>16}{:>16}",
+        "delay", "size", "binary_heap", "calendar", "ladder"
+    );
+
+    for &delay in &[Delay::Exponential, Delay::Uniform, Delay::Readvert] {
+        for &size in &SIZES {
+            let timings: Vec<String> = KINDS
+                .iter()
+                .map(|&kind| {
+                    let elapsed = hold(kind, delay, size);
+                    let per_hold = elapsed.as_secs_f64() * 1e9 / HOLDS as f64;
+                    format!("{:.1} ns", per_hold)
+                })
+                .collect();
+
+            println!(
+                "{:<12}{:>10}{:>16}{:>16}{:>16}",
+                format!("{:?}", delay).to_lowercase(),
+                size,
+                timings[0],
+                timings[1],
+                timings[2]
+            );
+        }
+    }
+}
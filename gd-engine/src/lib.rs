@@ -1,98 +1,436 @@
 #![warn(clippy::all)]
 
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+mod observer;
+mod profile;
+mod queue;
+mod time;
+
+pub use self::observer::EngineObserver;
+pub use self::profile::{KindProfile, Profile};
+pub use self::queue::{
+    BinaryHeapQueue, CalendarQueue, EventQueue, EventWrapper, LadderQueue, QueueKind,
+};
+pub use self::time::{Ticks, Time};
+
+use self::profile::Profiler;
+
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
-#[derive(Debug)]
-struct EventWrapper<Event>
-where
-    Event: fmt::Debug,
-{
-    time: f64,
-    event: Event,
-}
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{self, Serialize, Serializer};
+use serde_derive::{Deserialize, Serialize};
 
-impl<Event> Eq for EventWrapper<Event> where Event: fmt::Debug {}
+/// Handle to a scheduled event; can be used to cancel the event before it is dispatched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EventHandle(u64);
 
-impl<Event> PartialEq for EventWrapper<Event>
-where
-    Event: fmt::Debug,
-{
-    fn eq(&self, other: &EventWrapper<Event>) -> bool {
-        self.time == other.time
-    }
-}
+/// Priority class of an event; events scheduled for the same time are dispatched
+/// in ascending order of their class, and in the order they were scheduled within
+/// the same class.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Priority(pub u8);
 
-impl<Event> PartialOrd for EventWrapper<Event>
-where
-    Event: fmt::Debug,
-{
-    fn partial_cmp(&self, other: &EventWrapper<Event>) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+type Factory<Event> = Box<dyn FnMut() -> Event>;
+
+struct Recurring<Event, T> {
+    interval: T,
+    priority: Priority,
+    factory: Factory<Event>,
+    // handle of the occurrence currently in the queue
+    next: EventHandle,
 }
 
-impl<Event> Ord for EventWrapper<Event>
+impl<Event, T> fmt::Debug for Recurring<Event, T>
 where
-    Event: fmt::Debug,
+    T: Time,
 {
-    fn cmp(&self, other: &EventWrapper<Event>) -> Ordering {
-        if self.time < other.time {
-            Ordering::Greater
-        } else if self.time > other.time {
-            Ordering::Less
-        } else {
-            Ordering::Equal
-        }
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Recurring")
+            .field("interval", &self.interval)
+            .field("priority", &self.priority)
+            .field("next", &self.next)
+            .finish()
     }
 }
 
 #[derive(Debug)]
-pub struct Engine<Event>
+pub struct Engine<Event, T = f64>
 where
     Event: fmt::Debug,
+    T: Time,
 {
-    now: f64,
-    events: BinaryHeap<EventWrapper<Event>>,
+    now: T,
+    next_handle: u64,
+    kind: QueueKind,
+    events: Box<dyn EventQueue<Event, T>>,
+    pending: HashSet<EventHandle>,
+    observers: Vec<Box<dyn EngineObserver<Event, T>>>,
+    // recurring events by the handle returned from schedule_every, and the
+    // other way round, by the handle of their next occurrence
+    recurring: HashMap<EventHandle, Recurring<Event, T>>,
+    occurrences: HashMap<EventHandle, EventHandle>,
+    profiler: Option<Profiler<Event>>,
 }
 
-impl<Event> Engine<Event>
+impl<Event, T> Engine<Event, T>
 where
-    Event: fmt::Debug,
+    Event: fmt::Debug + 'static,
+    T: Time,
 {
     pub fn new() -> Self {
         Default::default()
     }
 
-    pub fn schedule(&mut self, after: f64, event: Event) {
+    pub fn with_queue(kind: QueueKind) -> Self {
+        Self {
+            now: T::default(),
+            next_handle: 0,
+            kind,
+            events: kind.into_queue(),
+            pending: HashSet::new(),
+            observers: Vec::new(),
+            recurring: HashMap::new(),
+            occurrences: HashMap::new(),
+            profiler: None,
+        }
+    }
+
+    // `kind_of` names the kind of an event, which the counters are kept by
+    pub fn enable_profiling(&mut self, kind_of: fn(&Event) -> &'static str) {
+        let mut profiler = Profiler::new(kind_of);
+        profiler.on_queue_len(self.pending.len());
+        self.profiler = Some(profiler);
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profiler.as_ref().map(Profiler::profile)
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn EngineObserver<Event, T>>) {
+        self.observers.push(observer);
+    }
+
+    pub fn queue_kind(&self) -> QueueKind {
+        self.kind
+    }
+
+    pub fn schedule(&mut self, after: T, event: Event) -> EventHandle {
+        self.schedule_with_priority(after, Priority::default(), event)
+    }
+
+    pub fn schedule_with_priority(
+        &mut self,
+        after: T,
+        priority: Priority,
+        event: Event,
+    ) -> EventHandle {
+        debug_assert!(after >= T::default(), "event scheduled in the past");
+
+        let handle = EventHandle(self.next_handle);
+        let time = self.now + after;
+        self.next_handle += 1;
+
+        for observer in &mut self.observers {
+            observer.on_schedule(time, &event);
+        }
+
         self.events.push(EventWrapper {
-            time: self.now + after,
+            time,
+            priority,
+            handle,
             event,
         });
+        self.pending.insert(handle);
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.on_queue_len(self.pending.len());
+        }
+
+        handle
+    }
+
+    // the first occurrence is due after one interval; the returned handle
+    // stays valid, and can be used for cancelling, across all occurrences
+    pub fn schedule_every<F>(&mut self, interval: T, factory: F) -> EventHandle
+    where
+        F: FnMut() -> Event + 'static,
+    {
+        self.schedule_every_with_priority(interval, Priority::default(), factory)
+    }
+
+    pub fn schedule_every_with_priority<F>(
+        &mut self,
+        interval: T,
+        priority: Priority,
+        mut factory: F,
+    ) -> EventHandle
+    where
+        F: FnMut() -> Event + 'static,
+    {
+        assert!(
+            interval > T::default(),
+            "interval of a recurring event has to be positive"
+        );
+
+        let handle = EventHandle(self.next_handle);
+        self.next_handle += 1;
+
+        let next = self.schedule_with_priority(interval, priority, factory());
+        self.occurrences.insert(next, handle);
+        self.recurring.insert(
+            handle,
+            Recurring {
+                interval,
+                priority,
+                factory: Box::new(factory),
+                next,
+            },
+        );
+
+        handle
+    }
+
+    // cancelled events are removed lazily, i.e., they are dropped once they
+    // reach the front of the queue
+    pub fn cancel(&mut self, handle: EventHandle) -> bool {
+        if let Some(recurring) = self.recurring.remove(&handle) {
+            self.occurrences.remove(&recurring.next);
+            return self.pending.remove(&recurring.next);
+        }
+
+        self.pending.remove(&handle)
+    }
+
+    pub fn is_pending(&self, handle: EventHandle) -> bool {
+        self.pending.contains(&handle) || self.recurring.contains_key(&handle)
     }
 
     pub fn pop(&mut self) -> Option<Event> {
-        self.events.pop().map(|e| {
-            self.now = e.time;
-            e.event
-        })
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end_lap();
+        }
+
+        while let Some(e) = self.events.pop() {
+            if self.pending.remove(&e.handle) {
+                self.now = e.time;
+
+                for observer in &mut self.observers {
+                    observer.on_dispatch(self.now, &e.event);
+                }
+
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.on_dispatch(&e.event);
+                }
+
+                if let Some(handle) = self.occurrences.remove(&e.handle) {
+                    self.reschedule(handle);
+                }
+
+                return Some(e.event);
+            }
+        }
+
+        None
     }
 
-    pub fn now(&self) -> f64 {
+    // accounts for an event handled right away instead of being scheduled, so
+    // that observers and the profiler see it like any dispatched one; the rest
+    // of the wall-clock time of the enclosing event goes to this one
+    pub fn record_dispatch(&mut self, event: &Event) {
+        for observer in &mut self.observers {
+            observer.on_dispatch(self.now, event);
+        }
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end_lap();
+            profiler.on_dispatch(event);
+        }
+    }
+
+    // cancelled events at the front of the queue are dropped on the way
+    pub fn peek(&mut self) -> Option<&EventWrapper<Event, T>> {
+        loop {
+            let handle = self.events.peek()?.handle;
+
+            if self.pending.contains(&handle) {
+                break;
+            }
+
+            self.events.pop();
+        }
+
+        self.events.peek()
+    }
+
+    // pops the next event only if it is due at or before the horizon; otherwise,
+    // the event stays in the queue and the clock is advanced to the horizon
+    pub fn pop_until(&mut self, horizon: T) -> Option<Event> {
+        if self.peek().is_some_and(|e| e.time <= horizon) {
+            return self.pop();
+        }
+
+        if self.now < horizon {
+            self.now = horizon;
+        }
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end_lap();
+        }
+
+        None
+    }
+
+    pub fn step<F>(&mut self, handler: F) -> bool
+    where
+        F: FnOnce(&mut Self, Event),
+    {
+        match self.pop() {
+            Some(event) => {
+                handler(self, event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn run_until<F>(&mut self, horizon: T, mut handler: F)
+    where
+        F: FnMut(&mut Self, Event),
+    {
+        while let Some(event) = self.pop_until(horizon) {
+            handler(self, event);
+        }
+    }
+
+    pub fn run_while<P, F>(&mut self, mut predicate: P, mut handler: F)
+    where
+        P: FnMut(&EventWrapper<Event, T>) -> bool,
+        F: FnMut(&mut Self, Event),
+    {
+        while self.peek().is_some_and(&mut predicate) {
+            let event = self.pop().expect("event not found");
+            handler(self, event);
+        }
+    }
+
+    fn reschedule(&mut self, handle: EventHandle) {
+        let recurring = self
+            .recurring
+            .get_mut(&handle)
+            .expect("recurring event not found");
+        let (interval, priority) = (recurring.interval, recurring.priority);
+        let event = (recurring.factory)();
+
+        let next = self.schedule_with_priority(interval, priority, event);
+        self.recurring
+            .get_mut(&handle)
+            .expect("recurring event not found")
+            .next = next;
+        self.occurrences.insert(next, handle);
+    }
+
+    pub fn now(&self) -> T {
         self.now
     }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
 }
 
-impl<Event> Default for Engine<Event>
+// only the clock and the pending events are persisted; cancelled events
+// still sitting in the queue are dropped on the way, and observers have to be
+// registered again after deserializing; the same goes for profiling; factories
+// of recurring events cannot be persisted, hence serializing an engine with
+// any recurring events pending fails rather than silently dropping them
+#[derive(Serialize)]
+struct EngineStateRef<'a, Event, T>
 where
     Event: fmt::Debug,
+    T: Time,
 {
-    fn default() -> Self {
-        Self {
-            now: 0.0,
-            events: BinaryHeap::new(),
+    now: T,
+    next_handle: u64,
+    kind: QueueKind,
+    events: Vec<&'a EventWrapper<Event, T>>,
+}
+
+#[derive(Deserialize)]
+struct EngineState<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    now: T,
+    next_handle: u64,
+    kind: QueueKind,
+    events: Vec<EventWrapper<Event, T>>,
+}
+
+impl<Event, T> Serialize for Engine<Event, T>
+where
+    Event: fmt::Debug + Serialize,
+    T: Time + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if !self.recurring.is_empty() {
+            return Err(ser::Error::custom("recurring events cannot be serialized"));
         }
+
+        EngineStateRef {
+            now: self.now,
+            next_handle: self.next_handle,
+            kind: self.kind,
+            events: self
+                .events
+                .iter()
+                .filter(|e| self.pending.contains(&e.handle))
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, Event, T> Deserialize<'de> for Engine<Event, T>
+where
+    Event: fmt::Debug + Deserialize<'de> + 'static,
+    T: Time + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let state = EngineState::deserialize(deserializer)?;
+        let mut engine = Self::with_queue(state.kind);
+
+        engine.now = state.now;
+        engine.next_handle = state.next_handle;
+        for e in state.events {
+            engine.pending.insert(e.handle);
+            engine.events.push(e);
+        }
+
+        Ok(engine)
+    }
+}
+
+impl<Event, T> Default for Engine<Event, T>
+where
+    Event: fmt::Debug + 'static,
+    T: Time,
+{
+    fn default() -> Self {
+        Self::with_queue(QueueKind::default())
     }
 }
 
@@ -100,12 +438,16 @@ where
 mod tests {
     use super::*;
 
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
     use statrs::assert_almost_eq;
 
     #[test]
     pub fn event_queue() {
         let mut engine = Engine::new();
         assert!(engine.events.is_empty());
+        assert_eq!(engine.queue_kind(), QueueKind::BinaryHeap);
         assert_almost_eq!(engine.now(), 0.0, 1e-6);
 
         engine.schedule(2.0, 3);
@@ -123,4 +465,361 @@ mod tests {
 
         assert_eq!(engine.pop(), None);
     }
+
+    #[test]
+    pub fn cancel() {
+        let mut engine = Engine::new();
+
+        let h1 = engine.schedule(1.0, 1);
+        let h2 = engine.schedule(2.0, 2);
+        let h3 = engine.schedule(3.0, 3);
+
+        assert_eq!(engine.len(), 3);
+        assert!(engine.cancel(h2));
+        assert!(!engine.cancel(h2));
+        assert!(!engine.is_pending(h2));
+        assert_eq!(engine.len(), 2);
+
+        assert_eq!(engine.pop(), Some(1));
+        assert!(!engine.cancel(h1));
+
+        assert!(engine.is_pending(h3));
+        assert_eq!(engine.pop(), Some(3));
+        assert_almost_eq!(engine.now(), 3.0, 1e-6);
+
+        assert!(engine.is_empty());
+        assert_eq!(engine.pop(), None);
+    }
+
+    #[test]
+    pub fn simultaneous_events() {
+        let mut engine = Engine::new();
+
+        engine.schedule(1.0, 1);
+        engine.schedule(1.0, 2);
+        engine.schedule(0.5, 0);
+        engine.schedule(1.0, 3);
+
+        assert_eq!(engine.pop(), Some(0));
+        assert_eq!(engine.pop(), Some(1));
+        assert_eq!(engine.pop(), Some(2));
+        assert_eq!(engine.pop(), Some(3));
+    }
+
+    #[test]
+    pub fn priority_classes() {
+        let mut engine = Engine::new();
+
+        engine.schedule_with_priority(1.0, Priority(2), 4);
+        engine.schedule_with_priority(1.0, Priority(1), 2);
+        engine.schedule(1.0, 1);
+        engine.schedule_with_priority(1.0, Priority(1), 3);
+        engine.schedule_with_priority(0.5, Priority(2), 0);
+
+        assert_eq!(engine.pop(), Some(0));
+        assert_eq!(engine.pop(), Some(1));
+        assert_eq!(engine.pop(), Some(2));
+        assert_eq!(engine.pop(), Some(3));
+        assert_eq!(engine.pop(), Some(4));
+    }
+
+    #[test]
+    pub fn queue_kinds() {
+        for &kind in &[
+            QueueKind::BinaryHeap,
+            QueueKind::Calendar,
+            QueueKind::Ladder,
+        ] {
+            let mut engine = Engine::with_queue(kind);
+
+            engine.schedule_with_priority(1.0, Priority(1), 3);
+            engine.schedule(1.0, 2);
+            let handle = engine.schedule(0.7, 0);
+            engine.schedule(0.5, 1);
+            engine.schedule(2.0, 4);
+            engine.cancel(handle);
+
+            assert_eq!(engine.pop(), Some(1));
+            assert_eq!(engine.pop(), Some(2));
+            assert_eq!(engine.pop(), Some(3));
+            assert_eq!(engine.pop(), Some(4));
+            assert_eq!(engine.pop(), None);
+            assert_almost_eq!(engine.now(), 2.0, 1e-6);
+        }
+    }
+
+    #[test]
+    pub fn queue_kinds_with_cancellation() {
+        use rand::prelude::*;
+        use rand_chacha::ChaChaRng;
+
+        // a cancelled event popped on the way may be due after the events
+        // scheduled next
+        for &kind in &[QueueKind::Calendar, QueueKind::Ladder] {
+            let mut engine = Engine::with_queue(kind);
+            let handle = engine.schedule(100.0, 0);
+            engine.cancel(handle);
+            assert_eq!(engine.pop(), None);
+
+            engine.schedule(1.0, 1);
+            engine.schedule(60.0, 2);
+            assert_eq!(engine.pop(), Some(1));
+            assert_almost_eq!(engine.now(), 1.0, 1e-6);
+
+            // as may an event peeked at beyond the horizon
+            let mut engine = Engine::with_queue(kind);
+            engine.schedule(100.0, 0);
+            assert_eq!(engine.pop_until(50.0), None);
+
+            engine.schedule(1.0, 1);
+            assert_eq!(engine.pop(), Some(1));
+            assert_almost_eq!(engine.now(), 51.0, 1e-6);
+        }
+
+        // schedules, cancels and pops events at random, draining the queue
+        // every now and then, against the binary heap
+        let mut rng = ChaChaRng::seed_from_u64(42);
+
+        for &kind in &[QueueKind::Calendar, QueueKind::Ladder] {
+            for _ in 0..50 {
+                let mut reference = Engine::new();
+                let mut engine = Engine::with_queue(kind);
+                let mut handles = Vec::new();
+
+                for step in 0..500 {
+                    match rng.gen_range(0, 10) {
+                        0..=4 => {
+                            let after = rng.gen_range(0.0, 100.0);
+                            let h1 = reference.schedule(after, step);
+                            let h2 = engine.schedule(after, step);
+                            assert_eq!(h1, h2);
+                            handles.push(h1);
+                        }
+                        5 | 6 if !handles.is_empty() => {
+                            let handle = handles.swap_remove(rng.gen_range(0, handles.len()));
+                            assert_eq!(reference.cancel(handle), engine.cancel(handle));
+                        }
+                        7 => {
+                            let horizon = reference.now() + rng.gen_range(0.0, 50.0);
+                            assert_eq!(engine.pop_until(horizon), reference.pop_until(horizon));
+                        }
+                        8 => loop {
+                            let event = reference.pop();
+                            assert_eq!(engine.pop(), event);
+                            if event.is_none() {
+                                break;
+                            }
+                        },
+                        _ => assert_eq!(engine.pop(), reference.pop()),
+                    }
+
+                    assert_almost_eq!(engine.now(), reference.now(), 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn observers() {
+        type Log = Rc<RefCell<Vec<(&'static str, f64, i32)>>>;
+
+        #[derive(Debug)]
+        struct Recorder(Log);
+
+        impl EngineObserver<i32, f64> for Recorder {
+            fn on_schedule(&mut self, time: f64, event: &i32) {
+                self.0.borrow_mut().push(("schedule", time, *event));
+            }
+
+            fn on_dispatch(&mut self, time: f64, event: &i32) {
+                self.0.borrow_mut().push(("dispatch", time, *event));
+            }
+        }
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.add_observer(Box::new(Recorder(log.clone())));
+
+        engine.schedule(2.0, 2);
+        let handle = engine.schedule(0.5, 0);
+        engine.schedule(1.0, 1);
+        engine.cancel(handle);
+
+        assert_eq!(engine.pop(), Some(1));
+        engine.schedule(0.5, 3);
+        assert_eq!(engine.pop(), Some(3));
+        assert_eq!(engine.pop(), Some(2));
+
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                ("schedule", 2.0, 2),
+                ("schedule", 0.5, 0),
+                ("schedule", 1.0, 1),
+                ("dispatch", 1.0, 1),
+                ("schedule", 1.5, 3),
+                ("dispatch", 1.5, 3),
+                ("dispatch", 2.0, 2),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn run_until() {
+        let mut engine = Engine::new();
+        let mut handled = Vec::new();
+
+        engine.schedule(1.0, 1);
+        engine.schedule(2.0, 2);
+        engine.schedule(3.0, 3);
+
+        engine.run_until(2.0, |engine, event| {
+            if event == 1 {
+                engine.schedule(0.5, 4);
+            }
+            handled.push(event);
+        });
+
+        assert_eq!(handled, vec![1, 4, 2]);
+        assert_almost_eq!(engine.now(), 2.0, 1e-6);
+
+        // the first event beyond the horizon is left in the queue
+        engine.run_until(2.5, |_, event| handled.push(event));
+        assert_eq!(handled, vec![1, 4, 2]);
+        assert_almost_eq!(engine.now(), 2.5, 1e-6);
+        assert_eq!(engine.len(), 1);
+        assert_almost_eq!(engine.peek().map(|e| e.time()).unwrap(), 3.0, 1e-6);
+
+        assert!(engine.step(|_, event| handled.push(event)));
+        assert!(!engine.step(|_, event| handled.push(event)));
+        assert_eq!(handled, vec![1, 4, 2, 3]);
+        assert_almost_eq!(engine.now(), 3.0, 1e-6);
+    }
+
+    #[test]
+    pub fn run_while() {
+        let mut engine = Engine::new();
+        let mut handled = 0;
+
+        let handle = engine.schedule(0.5, 0);
+        engine.schedule(1.0, 1);
+        engine.cancel(handle);
+
+        engine.run_while(
+            |e| e.time() < 10.0,
+            |engine, event| {
+                engine.schedule(1.0, event + 1);
+                handled += 1;
+            },
+        );
+
+        assert_eq!(handled, 9);
+        assert_almost_eq!(engine.now(), 9.0, 1e-6);
+        assert_eq!(engine.peek().map(|e| *e.event()), Some(10));
+    }
+
+    #[test]
+    pub fn recurring_events() {
+        let mut engine = Engine::new();
+        let mut count = 0;
+        let handle = engine.schedule_every(1.0, move || {
+            count += 1;
+            count
+        });
+        engine.schedule(2.5, 0);
+
+        let mut handled = Vec::new();
+        engine.run_until(3.5, |engine, event| handled.push((engine.now(), event)));
+
+        assert_eq!(handled, vec![(1.0, 1), (2.0, 2), (2.5, 0), (3.0, 3)]);
+        assert!(engine.is_pending(handle));
+        assert_eq!(engine.len(), 1);
+
+        assert!(engine.cancel(handle));
+        assert!(!engine.is_pending(handle));
+        assert!(!engine.cancel(handle));
+        assert!(engine.is_empty());
+        assert_eq!(engine.pop(), None);
+    }
+
+    #[test]
+    pub fn profiling() {
+        let mut engine = Engine::new();
+        engine.enable_profiling(|&event| if event % 2 == 0 { "even" } else { "odd" });
+
+        for i in 0..4 {
+            engine.schedule(f64::from(i), i);
+        }
+        let handle = engine.schedule(0.5, 5);
+        engine.cancel(handle);
+        // cancelled events still in the queue do not count
+        engine.schedule(10.0, 7);
+
+        engine.run_until(10.0, |engine, event| {
+            if event == 3 {
+                engine.schedule(1.0, 4);
+            }
+        });
+
+        // events handled outside of the queue count as well
+        engine.record_dispatch(&9);
+
+        let profile = engine.profile().unwrap();
+        assert_eq!(profile.kinds["even"].count, 3);
+        assert_eq!(profile.kinds["odd"].count, 4);
+        assert_eq!(profile.num_events(), 7);
+        assert_eq!(profile.queue_high_water_mark, 5);
+    }
+
+    #[test]
+    pub fn ticks_clock() {
+        let mut engine: Engine<i32, Ticks> = Engine::new();
+
+        engine.schedule(Ticks::from_secs(0.3), 2);
+        engine.schedule(Ticks::from_secs(0.1), 1);
+        engine.schedule(Ticks::from_secs(0.2), 1);
+
+        assert_eq!(engine.pop(), Some(1));
+        engine.schedule(Ticks::from_secs(0.2), 3);
+
+        assert_eq!(engine.pop(), Some(1));
+        assert_eq!(engine.pop(), Some(2));
+        assert_eq!(engine.pop(), Some(3));
+        assert_eq!(engine.now(), Ticks::from_secs(0.3));
+    }
+
+    #[test]
+    pub fn serialize() {
+        let mut engine = Engine::with_queue(QueueKind::Ladder);
+
+        engine.schedule(1.0, 1);
+        let handle = engine.schedule(2.0, 2);
+        engine.schedule(2.0, 3);
+        engine.schedule(3.0, 4);
+        engine.cancel(handle);
+
+        assert_eq!(engine.pop(), Some(1));
+
+        let bytes = bincode::serialize(&engine).unwrap();
+        let mut restored: Engine<i32> = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(restored.queue_kind(), QueueKind::Ladder);
+        assert_almost_eq!(restored.now(), 1.0, 1e-6);
+        assert_eq!(restored.len(), 2);
+
+        let h1 = engine.schedule(2.0, 5);
+        let h2 = restored.schedule(2.0, 5);
+        assert_eq!(h1, h2);
+
+        assert_eq!(restored.pop(), Some(3));
+        assert_eq!(restored.pop(), Some(4));
+        assert_eq!(restored.pop(), Some(5));
+        assert_eq!(restored.pop(), None);
+
+        // recurring events would be restored as one-off ones
+        let handle = restored.schedule_every(1.0, || 6);
+        assert!(bincode::serialize(&restored).is_err());
+        restored.cancel(handle);
+        assert!(bincode::serialize(&restored).is_ok());
+    }
 }
@@ -0,0 +1,16 @@
+use std::fmt;
+
+use crate::time::Time;
+
+/// Hooks into the event loop of an `Engine`; both callbacks are no-ops by default.
+pub trait EngineObserver<Event, T>: fmt::Debug
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    // `time` is the time the event is due at
+    fn on_schedule(&mut self, _time: T, _event: &Event) {}
+
+    // `time` is the current time, i.e., the time the event was due at
+    fn on_dispatch(&mut self, _time: T, _event: &Event) {}
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde_derive::Serialize;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct KindProfile {
+    pub count: u64,
+    pub wall_clock: Duration,
+}
+
+/// Performance counters collected by an `Engine` with profiling enabled.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Profile {
+    pub kinds: BTreeMap<&'static str, KindProfile>,
+    pub queue_high_water_mark: usize,
+}
+
+impl Profile {
+    pub fn num_events(&self) -> u64 {
+        self.kinds.values().map(|kind| kind.count).sum()
+    }
+
+    pub fn wall_clock(&self) -> Duration {
+        self.kinds.values().map(|kind| kind.wall_clock).sum()
+    }
+}
+
+// the wall-clock time spent on an event is measured from the moment it is
+// dispatched until the engine is asked for the next one, and thus includes
+// the time it takes to schedule any follow-up events
+#[derive(Debug)]
+pub(crate) struct Profiler<Event> {
+    kind_of: fn(&Event) -> &'static str,
+    profile: Profile,
+    lap: Option<(&'static str, Instant)>,
+}
+
+impl<Event> Profiler<Event> {
+    pub(crate) fn new(kind_of: fn(&Event) -> &'static str) -> Self {
+        Self {
+            kind_of,
+            profile: Profile::default(),
+            lap: None,
+        }
+    }
+
+    pub(crate) fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    pub(crate) fn on_dispatch(&mut self, event: &Event) {
+        let kind = (self.kind_of)(event);
+        self.profile.kinds.entry(kind).or_default().count += 1;
+        self.lap = Some((kind, Instant::now()));
+    }
+
+    pub(crate) fn end_lap(&mut self) {
+        if let Some((kind, start)) = self.lap.take() {
+            self.profile.kinds.entry(kind).or_default().wall_clock += start.elapsed();
+        }
+    }
+
+    pub(crate) fn on_queue_len(&mut self, len: usize) {
+        self.profile.queue_high_water_mark = self.profile.queue_high_water_mark.max(len);
+    }
+}
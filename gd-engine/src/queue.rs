@@ -0,0 +1,341 @@
+mod calendar;
+mod ladder;
+
+pub use self::calendar::CalendarQueue;
+pub use self::ladder::LadderQueue;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::time::Time;
+use crate::{EventHandle, Priority};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventWrapper<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    pub(crate) time: T,
+    pub(crate) priority: Priority,
+    pub(crate) handle: EventHandle,
+    pub(crate) event: Event,
+}
+
+impl<Event, T> EventWrapper<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    pub fn time(&self) -> T {
+        self.time
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    pub fn handle(&self) -> EventHandle {
+        self.handle
+    }
+
+    pub fn event(&self) -> &Event {
+        &self.event
+    }
+}
+
+impl<Event, T> Eq for EventWrapper<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+}
+
+impl<Event, T> PartialEq for EventWrapper<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn eq(&self, other: &EventWrapper<Event, T>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<Event, T> PartialOrd for EventWrapper<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn partial_cmp(&self, other: &EventWrapper<Event, T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Event, T> Ord for EventWrapper<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn cmp(&self, other: &EventWrapper<Event, T>) -> Ordering {
+        // reversed since BinaryHeap is a max-heap, i.e., the event to be
+        // dispatched first is the greatest; ties are broken by priority
+        // class and then by insertion order
+        if self.time < other.time {
+            Ordering::Greater
+        } else if self.time > other.time {
+            Ordering::Less
+        } else {
+            (other.priority, other.handle).cmp(&(self.priority, self.handle))
+        }
+    }
+}
+
+// inserts an event into a vector kept sorted in the reverse order of dispatch,
+// i.e., the event to be dispatched next is always the last one
+fn insert_sorted<Event, T>(events: &mut Vec<EventWrapper<Event, T>>, event: EventWrapper<Event, T>)
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    let index = events.binary_search(&event).unwrap_or_else(|index| index);
+    events.insert(index, event);
+}
+
+pub trait EventQueue<Event, T>: fmt::Debug
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn push(&mut self, event: EventWrapper<Event, T>);
+    fn pop(&mut self) -> Option<EventWrapper<Event, T>>;
+    // takes `&mut self` so that lazy structures can be reorganised on the way
+    fn peek(&mut self) -> Option<&EventWrapper<Event, T>>;
+    fn len(&self) -> usize;
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a EventWrapper<Event, T>> + 'a>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueKind {
+    #[default]
+    BinaryHeap,
+    Calendar,
+    Ladder,
+}
+
+impl QueueKind {
+    pub fn into_queue<Event, T>(self) -> Box<dyn EventQueue<Event, T>>
+    where
+        Event: fmt::Debug + 'static,
+        T: Time,
+    {
+        match self {
+            QueueKind::BinaryHeap => Box::new(BinaryHeapQueue::new()),
+            QueueKind::Calendar => Box::new(CalendarQueue::new()),
+            QueueKind::Ladder => Box::new(LadderQueue::new()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BinaryHeapQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    heap: BinaryHeap<EventWrapper<Event, T>>,
+}
+
+impl<Event, T> BinaryHeapQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<Event, T> Default for BinaryHeapQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn default() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<Event, T> EventQueue<Event, T> for BinaryHeapQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn push(&mut self, event: EventWrapper<Event, T>) {
+        self.heap.push(event)
+    }
+
+    fn pop(&mut self) -> Option<EventWrapper<Event, T>> {
+        self.heap.pop()
+    }
+
+    fn peek(&mut self) -> Option<&EventWrapper<Event, T>> {
+        self.heap.peek()
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a EventWrapper<Event, T>> + 'a> {
+        Box::new(self.heap.iter())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use rand::distributions::{Distribution, Exp, Uniform};
+    use rand::prelude::*;
+    use rand_chacha::ChaChaRng;
+
+    type Delay = Box<dyn Fn(&mut ChaChaRng) -> f64>;
+
+    pub(crate) fn wrap(time: f64, priority: u8, handle: u64) -> EventWrapper<u64, f64> {
+        EventWrapper {
+            time,
+            priority: Priority(priority),
+            handle: EventHandle(handle),
+            event: handle,
+        }
+    }
+
+    // pops and pushes events at random against a binary heap used as the
+    // reference implementation; the dispatch order has to be identical
+    pub(crate) fn check_against_binary_heap(mut queue: Box<dyn EventQueue<u64, f64>>) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let mut reference = BinaryHeapQueue::new();
+        let mut now = 0.0;
+        let mut handle = 0;
+
+        let delays: Vec<Delay> = vec![
+            Box::new(|rng| Exp::new(1.0).sample(rng)),
+            Box::new(|rng| Uniform::new(0.0, 1000.0).sample(rng)),
+            Box::new(|rng| *[0.0, 60.0, 3600.0].choose(rng).unwrap()),
+        ];
+
+        for delay in &delays {
+            for step in 0..20_000 {
+                let push = match step % 5000 {
+                    s if s < 2500 => rng.gen_bool(0.8),
+                    _ => rng.gen_bool(0.2),
+                };
+
+                if push {
+                    let time = now + delay(&mut rng);
+                    let priority = rng.gen_range(0, 3);
+                    queue.push(wrap(time, priority, handle));
+                    reference.push(wrap(time, priority, handle));
+                    handle += 1;
+                } else {
+                    assert_eq!(
+                        reference.peek().map(|e| e.event),
+                        queue.peek().map(|e| e.event)
+                    );
+
+                    let expected = reference.pop();
+                    let actual = queue.pop();
+
+                    assert_eq!(
+                        expected.as_ref().map(|e| e.event),
+                        actual.as_ref().map(|e| e.event)
+                    );
+
+                    if let Some(e) = expected {
+                        now = e.time;
+                    }
+                }
+
+                assert_eq!(queue.len(), reference.len());
+            }
+        }
+
+        while let Some(e) = reference.pop() {
+            assert_eq!(queue.pop().map(|e| e.event), Some(e.event));
+        }
+
+        assert!(queue.is_empty());
+        assert!(queue.pop().is_none());
+    }
+
+    // events pushed after the queue has been drained may be due before the
+    // last one popped, as the engine pops cancelled events on the way
+    pub(crate) fn check_push_after_drain(mut queue: Box<dyn EventQueue<u64, f64>>) {
+        queue.push(wrap(100.0, 0, 0));
+        assert_eq!(queue.pop().map(|e| e.event), Some(0));
+        assert!(queue.pop().is_none());
+
+        queue.push(wrap(1.0, 0, 1));
+        queue.push(wrap(60.0, 0, 2));
+        assert_eq!(queue.pop().map(|e| e.event), Some(1));
+        assert_eq!(queue.pop().map(|e| e.event), Some(2));
+
+        // and likewise when the queue gets resized on the way
+        for i in 3..20 {
+            queue.push(wrap(1000.0 + f64::from(i as u32), 0, i));
+        }
+        for i in 3..20 {
+            assert_eq!(queue.pop().map(|e| e.event), Some(i));
+        }
+        queue.push(wrap(500.0, 0, 20));
+        queue.push(wrap(30.0, 0, 21));
+
+        assert_eq!(queue.pop().map(|e| e.event), Some(21));
+
+        // peeking must not skip events pushed afterwards either
+        assert_eq!(queue.peek().map(|e| e.event), Some(20));
+        queue.push(wrap(200.0, 0, 22));
+        assert_eq!(queue.pop().map(|e| e.event), Some(22));
+        assert_eq!(queue.pop().map(|e| e.event), Some(20));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn binary_heap_queue() {
+        let mut queue = BinaryHeapQueue::new();
+        queue.push(wrap(1.0, 0, 0));
+        queue.push(wrap(0.5, 0, 1));
+        queue.push(wrap(1.0, 0, 2));
+
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.iter().count(), 3);
+        assert_eq!(queue.pop().map(|e| e.event), Some(1));
+        assert_eq!(queue.pop().map(|e| e.event), Some(0));
+        assert_eq!(queue.pop().map(|e| e.event), Some(2));
+        assert!(queue.is_empty());
+
+        check_push_after_drain(Box::new(BinaryHeapQueue::new()));
+    }
+
+    #[test]
+    fn insert_sorted() {
+        let mut events = Vec::new();
+        super::insert_sorted(&mut events, wrap(1.0, 0, 0));
+        super::insert_sorted(&mut events, wrap(0.5, 0, 1));
+        super::insert_sorted(&mut events, wrap(1.0, 1, 2));
+        super::insert_sorted(&mut events, wrap(1.0, 0, 3));
+
+        let order: Vec<u64> = events.iter().rev().map(|e| e.event).collect();
+        assert_eq!(order, vec![1, 0, 3, 2]);
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+
+use super::{insert_sorted, EventQueue, EventWrapper};
+use crate::time::Time;
+
+// Calendar queue after R. Brown, "Calendar queues: a fast O(1) priority queue
+// implementation for the simulation event set problem", CACM 31(10), 1988.
+//
+// Events are hashed into buckets ("days") of fixed width by their time; the
+// buckets are scanned cyclically, and the number of buckets and their width
+// are recalculated whenever the queue doubles or halves in size.
+#[derive(Debug)]
+pub struct CalendarQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    buckets: Vec<Vec<EventWrapper<Event, T>>>,
+    width: f64,
+    // index of the day currently dequeued, counted from time zero rather than
+    // modulo the number of buckets
+    current: u64,
+    last_time: f64,
+    len: usize,
+}
+
+impl<Event, T> CalendarQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    const MIN_BUCKETS: usize = 2;
+    const SAMPLE_SIZE: usize = 25;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn day(&self, time: T) -> u64 {
+        (time.as_secs() / self.width) as u64
+    }
+
+    fn bucket(&self, day: u64) -> usize {
+        (day % self.buckets.len() as u64) as usize
+    }
+
+    fn take(&mut self, bucket: usize) -> Option<EventWrapper<Event, T>> {
+        let event = self.buckets[bucket].pop()?;
+        self.len -= 1;
+        self.last_time = event.time.as_secs();
+
+        if self.buckets.len() > Self::MIN_BUCKETS && self.len < self.buckets.len() / 2 {
+            self.resize(self.buckets.len() / 2);
+        }
+
+        Some(event)
+    }
+
+    // finds the bucket holding the next event along with its day; the current
+    // day is only moved forward once the event is taken, so that peeking does
+    // not skip events pushed in the meantime
+    fn find_next(&self) -> Option<(usize, u64)> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // scan a year's worth of days starting from the current one
+        for current in self.current..self.current + self.buckets.len() as u64 {
+            let bucket = self.bucket(current);
+
+            if let Some(event) = self.buckets[bucket].last() {
+                if self.day(event.time) <= current {
+                    return Some((bucket, current));
+                }
+            }
+        }
+
+        // the calendar is sparse; fall back to a direct search for the earliest event
+        let bucket = (0..self.buckets.len())
+            .filter(|&bucket| !self.buckets[bucket].is_empty())
+            .max_by(|&x, &y| self.buckets[x].last().cmp(&self.buckets[y].last()))
+            .expect("non-empty bucket not found");
+        let day = self.day(self.buckets[bucket].last().unwrap().time);
+
+        Some((bucket, day))
+    }
+
+    fn estimate_width(&self) -> Option<f64> {
+        // average separation of the events at the head of the queue, ignoring
+        // outliers, as in the original paper
+        let mut times: Vec<f64> = self
+            .buckets
+            .iter()
+            .flat_map(|bucket| bucket.iter().map(|e| e.time.as_secs()))
+            .collect();
+
+        if times.len() < 2 {
+            return None;
+        }
+
+        let sample_size = times.len().min(Self::SAMPLE_SIZE);
+        let by_time = |x: &f64, y: &f64| x.partial_cmp(y).expect("event time is NaN");
+        if sample_size < times.len() {
+            times.select_nth_unstable_by(sample_size, by_time);
+            times.truncate(sample_size);
+        }
+        times.sort_unstable_by(by_time);
+
+        let separations: Vec<f64> = times.windows(2).map(|w| w[1] - w[0]).collect();
+        let mean = separations.iter().sum::<f64>() / separations.len() as f64;
+        let (sum, count) = separations
+            .iter()
+            .filter(|&&s| s <= 2.0 * mean)
+            .fold((0.0, 0), |(sum, count), s| (sum + s, count + 1));
+
+        let width = 3.0 * sum / f64::from(count);
+        if width > 0.0 {
+            Some(width)
+        } else {
+            None
+        }
+    }
+
+    fn resize(&mut self, num_buckets: usize) {
+        if let Some(width) = self.estimate_width() {
+            self.width = width;
+        }
+
+        let events: Vec<_> = self.buckets.drain(..).flatten().collect();
+        self.buckets = (0..num_buckets).map(|_| Vec::new()).collect();
+        self.current = (self.last_time / self.width) as u64;
+
+        for event in events {
+            let day = self.day(event.time);
+            self.current = self.current.min(day);
+
+            let bucket = self.bucket(day);
+            insert_sorted(&mut self.buckets[bucket], event);
+        }
+    }
+}
+
+impl<Event, T> Default for CalendarQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn default() -> Self {
+        Self {
+            buckets: (0..Self::MIN_BUCKETS).map(|_| Vec::new()).collect(),
+            width: 1.0,
+            current: 0,
+            last_time: 0.0,
+            len: 0,
+        }
+    }
+}
+
+impl<Event, T> EventQueue<Event, T> for CalendarQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn push(&mut self, event: EventWrapper<Event, T>) {
+        // the engine drops cancelled events by popping them, which may move
+        // the current day past events scheduled afterwards
+        let day = self.day(event.time);
+        self.current = self.current.min(day);
+
+        let bucket = self.bucket(day);
+        insert_sorted(&mut self.buckets[bucket], event);
+        self.len += 1;
+
+        if self.len > 2 * self.buckets.len() {
+            self.resize(2 * self.buckets.len());
+        }
+    }
+
+    fn pop(&mut self) -> Option<EventWrapper<Event, T>> {
+        let (bucket, day) = self.find_next()?;
+        self.current = day;
+        self.take(bucket)
+    }
+
+    fn peek(&mut self) -> Option<&EventWrapper<Event, T>> {
+        let (bucket, _) = self.find_next()?;
+        self.buckets[bucket].last()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a EventWrapper<Event, T>> + 'a> {
+        Box::new(self.buckets.iter().flat_map(|bucket| bucket.iter()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::queue::tests::{check_against_binary_heap, check_push_after_drain, wrap};
+
+    #[test]
+    fn dispatch_order() {
+        check_against_binary_heap(Box::new(CalendarQueue::new()));
+        check_push_after_drain(Box::new(CalendarQueue::new()));
+    }
+
+    #[test]
+    fn resize() {
+        let mut queue = CalendarQueue::new();
+
+        for i in 0..100 {
+            queue.push(wrap(f64::from(i) * 0.5, 0, i as u64));
+        }
+
+        assert_eq!(queue.buckets.len(), 64);
+        assert!((queue.width - 1.5).abs() < 1e-6);
+
+        for i in 0..90 {
+            assert_eq!(queue.pop().map(|e| e.event), Some(i));
+        }
+
+        assert_eq!(queue.len(), 10);
+        assert_eq!(queue.buckets.len(), 16);
+    }
+}
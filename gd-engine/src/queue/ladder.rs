@@ -0,0 +1,313 @@
+use std::f64;
+use std::fmt;
+use std::mem;
+
+use super::{insert_sorted, EventQueue, EventWrapper};
+use crate::time::Time;
+
+// Ladder queue after W. T. Tang, R. S. M. Goh and I. L.-J. Thng, "Ladder queue:
+// An O(1) priority queue structure for large-scale discrete event simulation",
+// ACM TOMACS 15(3), 2005.
+//
+// Far-future events are kept unsorted in the top; they are spread over the
+// buckets of a rung once the rest of the queue is exhausted. Buckets which are
+// too large to be sorted cheaply are split further into finer rungs, and only
+// the bucket at the very front of the queue ends up sorted in the bottom.
+#[derive(Debug)]
+pub struct LadderQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    top: Vec<EventWrapper<Event, T>>,
+    top_min: f64,
+    top_max: f64,
+    top_start: f64,
+    rungs: Vec<Rung<Event, T>>,
+    bottom: Vec<EventWrapper<Event, T>>,
+    len: usize,
+}
+
+#[derive(Debug)]
+struct Rung<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    buckets: Vec<Vec<EventWrapper<Event, T>>>,
+    start: f64,
+    width: f64,
+    current: usize,
+    len: usize,
+}
+
+impl<Event, T> Rung<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn new(start: f64, width: f64, num_buckets: usize) -> Self {
+        Self {
+            buckets: (0..num_buckets).map(|_| Vec::new()).collect(),
+            start,
+            width,
+            current: 0,
+            len: 0,
+        }
+    }
+
+    fn current_start(&self) -> f64 {
+        self.start + self.current as f64 * self.width
+    }
+
+    fn bucket(&self, time: f64) -> usize {
+        ((time - self.start) / self.width) as usize
+    }
+
+    // the decision is based on the bucket index rather than on the time the
+    // current bucket starts at, so that rounding errors cannot send events
+    // scheduled for the same time to different rungs
+    fn accepts(&self, time: f64) -> bool {
+        self.current < self.buckets.len() && self.bucket(time) >= self.current
+    }
+
+    fn push(&mut self, event: EventWrapper<Event, T>) {
+        let bucket = self.bucket(event.time.as_secs());
+        let bucket = bucket.max(self.current).min(self.buckets.len() - 1);
+
+        self.buckets[bucket].push(event);
+        self.len += 1;
+    }
+
+    fn take_current(&mut self) -> (f64, Vec<EventWrapper<Event, T>>) {
+        while self.buckets[self.current].is_empty() {
+            self.current += 1;
+        }
+
+        let start = self.current_start();
+        let bucket = mem::take(&mut self.buckets[self.current]);
+        self.current += 1;
+        self.len -= bucket.len();
+
+        (start, bucket)
+    }
+}
+
+impl<Event, T> LadderQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    const THRESHOLD: usize = 50;
+    const MAX_RUNGS: usize = 8;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // spreads the events over a new rung starting at `start`; gives the events
+    // back if the ladder cannot grow any further
+    fn spawn_rung(
+        &mut self,
+        start: f64,
+        width: f64,
+        num_buckets: usize,
+        events: Vec<EventWrapper<Event, T>>,
+    ) -> Result<(), Vec<EventWrapper<Event, T>>> {
+        if self.rungs.len() >= Self::MAX_RUNGS || width <= 0.0 || !width.is_finite() {
+            return Err(events);
+        }
+
+        let mut rung = Rung::new(start, width, num_buckets);
+        for event in events {
+            rung.push(event);
+        }
+        self.rungs.push(rung);
+
+        Ok(())
+    }
+
+    fn fill_bottom(&mut self, mut events: Vec<EventWrapper<Event, T>>) {
+        events.sort_unstable();
+        self.bottom = events;
+    }
+
+    fn refill_bottom(&mut self) {
+        loop {
+            while self.rungs.last().is_some_and(|rung| rung.len == 0) {
+                self.rungs.pop();
+            }
+
+            let events = if self.rungs.is_empty() {
+                if self.top.is_empty() {
+                    // nothing left, hence every new event can go to the top
+                    self.top_start = f64::NEG_INFINITY;
+                    return;
+                }
+
+                let events = mem::take(&mut self.top);
+                let start = self.top_min;
+                let width = (self.top_max - self.top_min) / events.len() as f64;
+                let num_buckets = events.len() + 1;
+
+                self.top_min = f64::INFINITY;
+                self.top_max = f64::NEG_INFINITY;
+                self.top_start = start + width * num_buckets as f64;
+
+                match self.spawn_rung(start, width, num_buckets, events) {
+                    Ok(()) => continue,
+                    Err(events) => {
+                        // all events are simultaneous
+                        self.top_start = start;
+                        events
+                    }
+                }
+            } else {
+                let rung = self.rungs.last_mut().expect("rung not found");
+                let width = rung.width;
+                let (start, events) = rung.take_current();
+
+                if events.len() <= Self::THRESHOLD {
+                    events
+                } else {
+                    let num_buckets = events.len();
+                    let width = width / num_buckets as f64;
+
+                    match self.spawn_rung(start, width, num_buckets, events) {
+                        Ok(()) => continue,
+                        Err(events) => events,
+                    }
+                }
+            };
+
+            self.fill_bottom(events);
+            return;
+        }
+    }
+}
+
+impl<Event, T> Default for LadderQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn default() -> Self {
+        Self {
+            top: Vec::new(),
+            top_min: f64::INFINITY,
+            top_max: f64::NEG_INFINITY,
+            top_start: f64::NEG_INFINITY,
+            rungs: Vec::new(),
+            bottom: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<Event, T> EventQueue<Event, T> for LadderQueue<Event, T>
+where
+    Event: fmt::Debug,
+    T: Time,
+{
+    fn push(&mut self, event: EventWrapper<Event, T>) {
+        let time = event.time.as_secs();
+        self.len += 1;
+
+        if time > self.top_start {
+            self.top_min = self.top_min.min(time);
+            self.top_max = self.top_max.max(time);
+            self.top.push(event);
+            return;
+        }
+
+        if let Some(rung) = self.rungs.iter_mut().find(|rung| rung.accepts(time)) {
+            rung.push(event);
+            return;
+        }
+
+        insert_sorted(&mut self.bottom, event);
+
+        // keep the bottom cheap to insert into by spreading it over a new rung
+        if self.bottom.len() > Self::THRESHOLD {
+            let start = self.bottom.last().expect("event not found").time.as_secs();
+            let end = self.bottom[0].time.as_secs();
+            let events = mem::take(&mut self.bottom);
+            let width = (end - start) / events.len() as f64;
+            let num_buckets = events.len() + 1;
+
+            if let Err(events) = self.spawn_rung(start, width, num_buckets, events) {
+                self.bottom = events;
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<EventWrapper<Event, T>> {
+        if self.bottom.is_empty() {
+            self.refill_bottom();
+        }
+
+        let event = self.bottom.pop()?;
+        self.len -= 1;
+
+        Some(event)
+    }
+
+    fn peek(&mut self) -> Option<&EventWrapper<Event, T>> {
+        if self.bottom.is_empty() {
+            self.refill_bottom();
+        }
+
+        self.bottom.last()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a EventWrapper<Event, T>> + 'a> {
+        Box::new(
+            self.top
+                .iter()
+                .chain(
+                    self.rungs
+                        .iter()
+                        .flat_map(|rung| rung.buckets.iter().flat_map(|bucket| bucket.iter())),
+                )
+                .chain(self.bottom.iter()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::queue::tests::{check_against_binary_heap, check_push_after_drain, wrap};
+
+    #[test]
+    fn dispatch_order() {
+        check_against_binary_heap(Box::new(LadderQueue::new()));
+        check_push_after_drain(Box::new(LadderQueue::new()));
+    }
+
+    #[test]
+    fn spawn_rungs() {
+        let mut queue = LadderQueue::new();
+
+        for i in 0..1000 {
+            queue.push(wrap(f64::from(i % 100), 0, i as u64));
+        }
+
+        assert_eq!(queue.top.len(), 1000);
+        assert_eq!(queue.pop().map(|e| e.event), Some(0));
+        assert!(!queue.rungs.is_empty());
+
+        // events at the same time are dispatched in the order of insertion
+        for i in 1..10 {
+            assert_eq!(queue.pop().map(|e| e.event), Some(i * 100));
+        }
+        assert_eq!(queue.pop().map(|e| e.event), Some(1));
+        assert_eq!(queue.len(), 989);
+        assert_eq!(queue.iter().count(), 989);
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+use std::ops::{Add, Sub};
+
+use serde_derive::{Deserialize, Serialize};
+
+pub trait Time:
+    Copy + Default + fmt::Debug + PartialOrd + Add<Output = Self> + Sub<Output = Self> + 'static
+{
+    fn from_secs(secs: f64) -> Self;
+    fn as_secs(self) -> f64;
+}
+
+impl Time for f64 {
+    fn from_secs(secs: f64) -> Self {
+        secs
+    }
+
+    fn as_secs(self) -> f64 {
+        self
+    }
+}
+
+/// Integer simulation clock counting microseconds; unlike `f64`, it does not drift
+/// over long runs, so ties between events are detected exactly.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Ticks(pub u64);
+
+impl Ticks {
+    pub const PER_SECOND: u64 = 1_000_000;
+}
+
+impl Add for Ticks {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Ticks(self.0 + other.0)
+    }
+}
+
+impl Sub for Ticks {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Ticks(self.0 - other.0)
+    }
+}
+
+impl Time for Ticks {
+    fn from_secs(secs: f64) -> Self {
+        // negative durations saturate to zero
+        Ticks((secs * Self::PER_SECOND as f64).round() as u64)
+    }
+
+    fn as_secs(self) -> f64 {
+        self.0 as f64 / Self::PER_SECOND as f64
+    }
+}
+
+impl fmt::Display for Ticks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_secs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use statrs::assert_almost_eq;
+
+    #[test]
+    fn ticks_conversion() {
+        assert_eq!(Ticks::from_secs(1.0), Ticks(1_000_000));
+        assert_eq!(Ticks::from_secs(0.5e-6), Ticks(1));
+        assert_eq!(Ticks::from_secs(-1.0), Ticks(0));
+        assert_almost_eq!(Ticks(604_800_000_000).as_secs(), 604_800.0, 1e-9);
+    }
+
+    #[test]
+    fn ticks_do_not_drift() {
+        let step = Ticks::from_secs(0.1);
+        let mut now = Ticks::default();
+
+        for _ in 0..6_048_000 {
+            now = now + step;
+        }
+
+        assert_eq!(now, Ticks::from_secs(604_800.0));
+    }
+}
@@ -0,0 +1,524 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use gd_engine::{Profile, Ticks, Time};
+use log::error;
+use rand::prelude::*;
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+
+use crate::id::IdAllocator;
+use crate::metrics::Sample;
+use crate::provider;
+use crate::requestor;
+use crate::requestor::DefenceMechanismType;
+use crate::rng::SimRng;
+use crate::scenario::{Clock, SimulationParams};
+use crate::trace::{self, Divergence, JsonLinesSink, Record, Recorder};
+use crate::world::{StopCondition, StopReason, World};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SeedPolicy {
+    // the run `i` is seeded with `seed + i`
+    Sequential(u64),
+    // each run is seeded from the entropy of the OS
+    Entropy,
+}
+
+impl SeedPolicy {
+    pub fn rng(self, run_id: u64) -> SimRng {
+        match self {
+            SeedPolicy::Sequential(seed) => SimRng::seed_from_u64(seed + run_id),
+            SeedPolicy::Entropy => SimRng::from_entropy(),
+        }
+    }
+}
+
+impl From<Option<u64>> for SeedPolicy {
+    fn from(seed: Option<u64>) -> Self {
+        seed.map_or(SeedPolicy::Entropy, SeedPolicy::Sequential)
+    }
+}
+
+#[derive(Debug)]
+pub struct RunResult {
+    pub run_id: u64,
+    pub requestors: Vec<requestor::Stats>,
+    pub providers: Vec<provider::Stats>,
+    pub profile: Option<Profile>,
+    // market state over time; empty unless sampling is enabled
+    pub samples: Vec<Sample>,
+    // simulated time at which the run ended, and why
+    pub run_length: f64,
+    pub stop_reason: StopReason,
+}
+
+#[derive(Debug)]
+struct Checkpoints {
+    interval: f64,
+    dir: PathBuf,
+}
+
+/// Replications of a simulation scenario; each run builds its own `World` from
+/// the scenario, seeded according to the seed policy.
+#[derive(Debug)]
+pub struct Experiment {
+    params: SimulationParams,
+    repetitions: usize,
+    seed_policy: SeedPolicy,
+    defence_mechanism: DefenceMechanismType,
+    checkpoints: Option<Checkpoints>,
+    resume_from: Option<PathBuf>,
+    profiling: bool,
+    sampling: Option<f64>,
+    // directory the domain events of each run are traced to
+    tracing: Option<PathBuf>,
+}
+
+impl Experiment {
+    pub fn new(params: SimulationParams, repetitions: usize) -> Self {
+        Self {
+            seed_policy: params.seed.into(),
+            params,
+            repetitions,
+            defence_mechanism: DefenceMechanismType::Redundancy,
+            checkpoints: None,
+            resume_from: None,
+            profiling: false,
+            sampling: None,
+            tracing: None,
+        }
+    }
+
+    pub fn params(&self) -> &SimulationParams {
+        &self.params
+    }
+
+    pub fn repetitions(&self) -> usize {
+        self.repetitions
+    }
+
+    pub fn set_seed_policy(&mut self, seed_policy: SeedPolicy) {
+        self.seed_policy = seed_policy;
+    }
+
+    pub fn set_defence_mechanism(&mut self, defence_mechanism: DefenceMechanismType) {
+        self.defence_mechanism = defence_mechanism;
+    }
+
+    pub fn enable_checkpoints<P>(&mut self, interval: f64, dir: P)
+    where
+        P: AsRef<Path>,
+    {
+        self.checkpoints = Some(Checkpoints {
+            interval,
+            dir: dir.as_ref().to_path_buf(),
+        });
+    }
+
+    // runs with a checkpoint saved in `dir` pick up from it rather than start afresh
+    pub fn resume_from<P>(&mut self, dir: P)
+    where
+        P: AsRef<Path>,
+    {
+        self.resume_from = Some(dir.as_ref().to_path_buf());
+    }
+
+    pub fn enable_profiling(&mut self) {
+        self.profiling = true;
+    }
+
+    // samples the market state of each run every `interval` simulated seconds
+    pub fn enable_sampling(&mut self, interval: f64) {
+        self.sampling = Some(interval);
+    }
+
+    pub fn enable_tracing<P>(&mut self, dir: P)
+    where
+        P: AsRef<Path>,
+    {
+        self.tracing = Some(dir.as_ref().to_path_buf());
+    }
+
+    pub fn trace_path(&self, dir: &Path, run_id: u64) -> PathBuf {
+        let seed = self.params.seed.map(|seed| seed.to_string());
+        let name = format!("trace_{}_{}", run_id, seed.unwrap_or_default());
+
+        dir.join(name).with_extension("jsonl")
+    }
+
+    pub fn checkpoint_path(&self, dir: &Path, run_id: u64) -> PathBuf {
+        let seed = self.params.seed.map(|seed| seed.to_string());
+        let name = format!("checkpoint_{}_{}", run_id, seed.unwrap_or_default());
+
+        dir.join(name).with_extension("bin")
+    }
+
+    pub fn build_world<T>(&self, run_id: u64) -> World<SimRng, T>
+    where
+        T: Time,
+    {
+        let mut rng = self.seed_policy.rng(run_id);
+        let mut ids = IdAllocator::new();
+
+        // keep the ids given in the scenario out of the way of the allocated ones
+        let requestor_ids = self.params.requestors.iter().flatten().map(|s| s.id());
+        let provider_ids = self.params.providers.iter().flatten().map(|s| s.id());
+        for id in requestor_ids.chain(provider_ids).flatten() {
+            ids.reserve(id);
+        }
+
+        // create pre-specified actors
+        let mut requestors = Vec::new();
+        if let Some(rs) = &self.params.requestors {
+            for spec in rs {
+                requestors.push(spec.as_requestor(&mut ids, &mut rng, self.defence_mechanism));
+            }
+        }
+
+        let mut providers = Vec::new();
+        if let Some(ps) = &self.params.providers {
+            for spec in ps {
+                providers.push(spec.as_provider(&mut ids));
+            }
+        }
+
+        // create random actors
+        let mut requestor_departures = Vec::new();
+        if let Some(sources) = &self.params.requestor_sources {
+            for source in sources {
+                for requestor in source
+                    .iter(&mut ids, &mut rng, self.defence_mechanism)
+                    .collect::<Vec<_>>()
+                {
+                    if let Some(lifetime) = source.sample_lifetime(&mut rng) {
+                        requestor_departures.push((*requestor.id(), lifetime));
+                    }
+                    requestors.push(requestor);
+                }
+            }
+        }
+
+        let mut provider_departures = Vec::new();
+        if let Some(sources) = &self.params.provider_sources {
+            for source in sources {
+                for provider in source.iter(&mut ids, &mut rng).collect::<Vec<_>>() {
+                    if let Some(lifetime) = source.sample_lifetime(&mut rng) {
+                        provider_departures.push((*provider.id(), lifetime));
+                    }
+                    providers.push(provider);
+                }
+            }
+        }
+
+        // create the simulation world; aka the marketplace
+        let event_queue = self.params.event_queue.unwrap_or_default();
+        let mut world = World::with_event_queue(rng, event_queue);
+        world.set_ids(ids);
+
+        if let Some(latency) = &self.params.latency {
+            world.set_latency(latency.clone());
+        }
+
+        if let Some(visibility) = &self.params.visibility {
+            world.set_visibility(visibility.clone());
+        }
+
+        if let Some(settlement) = &self.params.settlement {
+            world.set_settlement(settlement.clone());
+        }
+
+        // append actors
+        world.append_requestors(requestors);
+        world.append_providers(providers);
+
+        // set up churn
+        for (requestor_id, lifetime) in requestor_departures {
+            world.schedule_requestor_departure(requestor_id, lifetime);
+        }
+
+        for (provider_id, lifetime) in provider_departures {
+            world.schedule_provider_departure(provider_id, lifetime);
+        }
+
+        if let Some(sources) = &self.params.requestor_sources {
+            for source in sources.iter().filter(|source| source.has_churn()) {
+                world.push_requestor_source(source.clone(), self.defence_mechanism);
+            }
+        }
+
+        if let Some(sources) = &self.params.provider_sources {
+            for source in sources.iter().filter(|source| source.has_churn()) {
+                world.push_provider_source(source.clone());
+            }
+        }
+
+        world
+    }
+
+    pub fn run_one(&self, run_id: u64) -> RunResult {
+        match self.params.clock.unwrap_or_default() {
+            Clock::Seconds => self.run_one_on::<f64>(run_id),
+            Clock::Ticks => self.run_one_on::<Ticks>(run_id),
+        }
+    }
+
+    fn run_one_on<T>(&self, run_id: u64) -> RunResult
+    where
+        T: Time + Serialize + DeserializeOwned,
+    {
+        let mut world = self
+            .resume::<T>(run_id)
+            .unwrap_or_else(|| self.build_world(run_id));
+
+        if let Some(checkpoints) = &self.checkpoints {
+            let path = self.checkpoint_path(&checkpoints.dir, run_id);
+            world.enable_checkpoints(checkpoints.interval, path);
+        }
+
+        if self.profiling {
+            world.enable_profiling();
+        }
+
+        if let Some(interval) = self.sampling {
+            world.enable_sampling(interval);
+        }
+
+        if let Some(dir) = &self.tracing {
+            let path = self.trace_path(dir, run_id);
+
+            match File::create(&path) {
+                Ok(file) => world.subscribe(Box::new(JsonLinesSink::new(BufWriter::new(file)))),
+                Err(err) => error!("could not create trace {}: {}", path.display(), err),
+            }
+        }
+
+        // run the simulation
+        let stop_reason = self.run_world(&mut world);
+        let run_length = world.now();
+
+        // gather statistics
+        let profile = world.profile().cloned();
+        let samples = world.samples().to_vec();
+        let (requestors, providers) = world.into_stats(run_id);
+
+        RunResult {
+            run_id,
+            requestors,
+            providers,
+            profile,
+            samples,
+            run_length,
+            stop_reason,
+        }
+    }
+
+    // re-runs the replication from scratch and compares its trace with the one
+    // recorded before; only runs with a fixed seed can be replayed
+    pub fn replay(&self, run_id: u64, expected: &[Record]) -> Option<Divergence> {
+        match self.params.clock.unwrap_or_default() {
+            Clock::Seconds => self.replay_on::<f64>(run_id, expected),
+            Clock::Ticks => self.replay_on::<Ticks>(run_id, expected),
+        }
+    }
+
+    fn replay_on<T>(&self, run_id: u64, expected: &[Record]) -> Option<Divergence>
+    where
+        T: Time,
+    {
+        let mut world = self.build_world::<T>(run_id);
+        let recorder = Recorder::default();
+        let records = recorder.records();
+        world.subscribe(Box::new(recorder));
+
+        self.run_world(&mut world);
+
+        let actual = records.borrow();
+        trace::diff(expected, &actual)
+    }
+
+    // runs the replications one after another, as the results are consumed
+    pub fn iter(&self) -> impl Iterator<Item = RunResult> + '_ {
+        (0..self.repetitions as u64).map(move |run_id| self.run_one(run_id))
+    }
+
+    // runs the replications in parallel; the callback is invoked from the
+    // worker threads as soon as each run finishes
+    pub fn for_each<F>(&self, callback: F)
+    where
+        F: Fn(RunResult) + Sync + Send,
+    {
+        (0..self.repetitions as u64)
+            .into_par_iter()
+            .for_each(|run_id| callback(self.run_one(run_id)));
+    }
+
+    // runs the replications in parallel; the results are ordered by run
+    pub fn run(&self) -> Vec<RunResult> {
+        (0..self.repetitions as u64)
+            .into_par_iter()
+            .map(|run_id| self.run_one(run_id))
+            .collect()
+    }
+
+    fn run_world<T>(&self, world: &mut World<SimRng, T>) -> StopReason
+    where
+        T: Time,
+    {
+        let mut conditions = Vec::new();
+
+        if let Some(stop) = &self.params.stop {
+            if stop.workload_done {
+                conditions.push(StopCondition::WorkloadDone);
+            }
+
+            if let Some(max_events) = stop.max_events {
+                conditions.push(StopCondition::MaxEvents(max_events));
+            }
+        }
+
+        world.run_with(self.params.duration, &conditions)
+    }
+
+    fn resume<T>(&self, run_id: u64) -> Option<World<SimRng, T>>
+    where
+        T: Time + Serialize + DeserializeOwned,
+    {
+        let path = self.checkpoint_path(self.resume_from.as_ref()?, run_id);
+
+        if !path.exists() {
+            return None;
+        }
+
+        match World::load_checkpoint(&path) {
+            Ok(world) => Some(world),
+            Err(err) => {
+                error!(
+                    "could not load checkpoint {}: {}; starting afresh",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::id::Id;
+
+    fn build_experiment() -> Experiment {
+        let params: SimulationParams = serde_json::from_str(
+            r#"{
+                "seed": 7,
+                "duration": 20000,
+                "requestor_sources": [{
+                    "requestor_count": 3,
+                    "max_price": { "fixed": 0.001 },
+                    "budget_factor": { "choice": [0.5, 1.0] },
+                    "subtask_count": { "fixed": 10 },
+                    "nominal_usage": { "fixed": 200 }
+                }],
+                "provider_sources": [{
+                    "provider_count": 4,
+                    "min_price": { "fixed": 0.00001 },
+                    "usage_factor": { "uniform": [0.1, 1.0] }
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        Experiment::new(params, 3)
+    }
+
+    #[test]
+    fn seed_policy() {
+        assert_eq!(SeedPolicy::from(Some(42)), SeedPolicy::Sequential(42));
+        assert_eq!(SeedPolicy::from(None), SeedPolicy::Entropy);
+    }
+
+    #[test]
+    fn replications() {
+        let experiment = build_experiment();
+
+        let results = experiment.run();
+        let run_ids: Vec<u64> = results.iter().map(|r| r.run_id).collect();
+        assert_eq!(run_ids, vec![0, 1, 2]);
+
+        // sequential and parallel runs of the same seeds give the same results
+        for (expected, actual) in results.iter().zip(experiment.iter()) {
+            assert_eq!(format!("{:?}", expected), format!("{:?}", actual));
+        }
+
+        let count = std::sync::atomic::AtomicUsize::new(0);
+        experiment.for_each(|result| {
+            assert!(result.run_id < 3);
+            assert!(result.profile.is_none());
+            count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        });
+        assert_eq!(count.into_inner(), 3);
+    }
+
+    #[test]
+    fn ticks_clock() {
+        let mut experiment = build_experiment();
+        experiment.params.clock = Some(Clock::Ticks);
+        let result = experiment.run_one(0);
+
+        assert_eq!(result.run_length, experiment.params.duration);
+        assert!(result
+            .requestors
+            .iter()
+            .any(|stats| stats.num_subtasks_computed > 0));
+    }
+
+    #[test]
+    fn deterministic_ids() {
+        let experiment = build_experiment();
+        let ids = |world: World<SimRng>| -> Vec<Id> {
+            world
+                .requestors()
+                .map(|requestor| *requestor.id())
+                .collect()
+        };
+
+        // other runs built in between do not shift the ids
+        let expected = ids(experiment.build_world(1));
+        experiment.build_world::<f64>(0);
+        assert_eq!(ids(experiment.build_world(1)), expected);
+    }
+
+    #[test]
+    fn replay() {
+        let experiment = build_experiment();
+
+        let mut world: World<SimRng> = experiment.build_world(1);
+        let recorder = Recorder::default();
+        let records = recorder.records();
+        world.subscribe(Box::new(recorder));
+        world.run(experiment.params.duration);
+
+        // traces survive the round trip through JSON intact
+        let json: Vec<String> = records
+            .borrow()
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap())
+            .collect();
+        let mut expected = trace::read_trace(json.join("\n").as_bytes()).unwrap();
+        assert!(!expected.is_empty());
+        assert_eq!(experiment.replay(1, &expected), None);
+
+        // a different seed gives a different trace
+        assert!(experiment.replay(2, &expected).is_some());
+
+        let index = expected.len() / 2;
+        expected[index].time += 1.0;
+        let divergence = experiment.replay(1, &expected).unwrap();
+        assert_eq!(divergence.index, index);
+    }
+}
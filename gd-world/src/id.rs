@@ -1,18 +1,15 @@
 use std::fmt;
-use std::sync::atomic::{AtomicUsize, Ordering};
 
-use serde_derive::Deserialize;
+use serde_derive::{Deserialize, Serialize};
 
-static BASE_VALUE: AtomicUsize = AtomicUsize::new(0);
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Id {
     value: usize,
 }
 
 impl Id {
-    pub fn new() -> Self {
-        Self::default()
+    pub(crate) fn from_value(value: usize) -> Self {
+        Self { value }
     }
 
     pub fn value(self) -> usize {
@@ -20,15 +17,54 @@ impl Id {
     }
 }
 
-impl Default for Id {
-    fn default() -> Self {
-        let value = BASE_VALUE.fetch_add(1, Ordering::SeqCst);
-        Self { value }
-    }
-}
-
 impl fmt::Display for Id {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.value)
     }
 }
+
+// hands out ids in sequence; each world, and the scenario build feeding it,
+// owns one, so a seeded run always gets the same ids however many other runs
+// go on in the same process
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IdAllocator {
+    next: usize,
+}
+
+impl IdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allocate(&mut self) -> Id {
+        let id = Id::from_value(self.next);
+        self.next += 1;
+        id
+    }
+
+    // makes sure an id assigned by hand, e.g., in a scenario file, is never
+    // handed out
+    pub fn reserve(&mut self, id: Id) {
+        self.next = self.next.max(id.value + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocate() {
+        let mut ids = IdAllocator::new();
+        assert_eq!(ids.allocate(), Id::from_value(0));
+        assert_eq!(ids.allocate(), Id::from_value(1));
+
+        ids.reserve(Id::from_value(5));
+        ids.reserve(Id::from_value(3));
+        assert_eq!(ids.allocate(), Id::from_value(6));
+
+        // separate allocators hand out the same sequence
+        let mut other = IdAllocator::new();
+        assert_eq!(other.allocate(), Id::from_value(0));
+    }
+}
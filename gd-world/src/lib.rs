@@ -1,18 +1,34 @@
 #![warn(clippy::all)]
 
+pub mod experiment;
 pub mod id;
 pub mod logger;
+pub mod metrics;
 pub mod provider;
 pub mod requestor;
+pub mod rng;
+pub mod scenario;
+pub mod settlement;
 pub mod task;
+pub mod trace;
+pub mod visibility;
 pub mod world;
 
 pub mod prelude {
-    pub use crate::id::Id;
+    pub use gd_engine::{Profile, QueueKind};
+
+    pub use crate::experiment::{Experiment, RunResult, SeedPolicy};
+    pub use crate::id::{Id, IdAllocator};
+    pub use crate::metrics::Sample;
     pub use crate::provider::{
         LinearUsageInflationProvider, Provider, RegularProvider, UndercutBudgetProvider,
     };
     pub use crate::requestor::{Requestor, TaskQueue};
+    pub use crate::rng::SimRng;
+    pub use crate::scenario::{Clock, SimulationParams};
+    pub use crate::settlement::SettlementModel;
     pub use crate::task::{SubTask, Task};
-    pub use crate::world::World;
+    pub use crate::trace::{DomainEvent, JsonLinesSink, Subscriber};
+    pub use crate::visibility::Visibility;
+    pub use crate::world::{StopCondition, StopReason, World};
 }
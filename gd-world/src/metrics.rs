@@ -0,0 +1,77 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::provider::Behaviour;
+
+// snapshot of the state of the market at a point in simulated time
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub time: f64,
+    // prices asked by the providers in the market; NaN if there are none
+    pub mean_price: f64,
+    pub median_price: f64,
+    // fraction of providers with all slots idle
+    pub idle_providers: f64,
+    // requestors with subtasks yet to be handed out
+    pub waiting_requestors: usize,
+    // subtasks handed out and not reported back on yet
+    pub subtasks_in_flight: usize,
+    // revenue earned so far, including providers which have left the market
+    pub revenue_regular: f64,
+    pub revenue_linear_usage_inflation: f64,
+    pub revenue_undercut_budget: f64,
+}
+
+impl Sample {
+    pub fn new(time: f64) -> Self {
+        Self {
+            time,
+            ..Self::default()
+        }
+    }
+
+    pub fn set_prices(&mut self, mut prices: Vec<f64>) {
+        if prices.is_empty() {
+            self.mean_price = f64::NAN;
+            self.median_price = f64::NAN;
+            return;
+        }
+
+        prices.sort_by(|x, y| x.partial_cmp(y).expect("price is NaN"));
+
+        let len = prices.len();
+        self.mean_price = prices.iter().sum::<f64>() / len as f64;
+        // both indices point at the middle element for an odd count
+        self.median_price = (prices[(len - 1) / 2] + prices[len / 2]) / 2.0;
+    }
+
+    pub fn add_revenue(&mut self, behaviour: Behaviour, revenue: f64) {
+        match behaviour {
+            Behaviour::Regular => self.revenue_regular += revenue,
+            Behaviour::LinearUsageInflation => self.revenue_linear_usage_inflation += revenue,
+            Behaviour::UndercutBudget => self.revenue_undercut_budget += revenue,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use statrs::assert_almost_eq;
+
+    #[test]
+    fn prices() {
+        let mut sample = Sample::new(0.0);
+
+        sample.set_prices(vec![3.0, 1.0, 2.0, 10.0]);
+        assert_almost_eq!(sample.mean_price, 4.0, 1e-9);
+        assert_almost_eq!(sample.median_price, 2.5, 1e-9);
+
+        sample.set_prices(vec![3.0, 1.0, 2.0]);
+        assert_almost_eq!(sample.median_price, 2.0, 1e-9);
+
+        sample.set_prices(Vec::new());
+        assert!(sample.mean_price.is_nan());
+        assert!(sample.median_price.is_nan());
+    }
+}
@@ -7,11 +7,13 @@ pub use self::regular::RegularProvider;
 pub use self::undercut_budget::UndercutBudgetProvider;
 
 use std::any::Any;
+use std::collections::{BTreeMap, VecDeque};
 use std::fmt;
 use std::ops;
 
-use gd_engine::Engine;
+use gd_engine::{Engine, EventHandle, Time};
 use log::debug;
+use rand::distributions::{Distribution, Exp};
 use serde_derive::{Deserialize, Serialize};
 
 use crate::id::Id;
@@ -52,12 +54,25 @@ pub struct Stats {
     pub num_subtasks_assigned: usize,
     pub num_subtasks_computed: usize,
     pub num_subtasks_cancelled: usize,
+    #[serde(default)]
+    pub num_subtasks_lost: usize,
+    #[serde(default)]
+    pub num_subtasks_timed_out: usize,
+    // amount billed, yet withheld by requestors
+    #[serde(default)]
+    pub revenue_lost: f64,
+    #[serde(default)]
+    pub num_subtasks_unpaid: usize,
+    // amount billed, and neither paid nor withheld yet
+    #[serde(default)]
+    pub receivables: f64,
 }
 
 pub trait Provider {
     type Rng: rand::Rng + 'static;
 
     fn report_usage(&self, rng: &mut Self::Rng, subtask: &SubTask, bid: f64) -> f64;
+    fn behaviour(&self) -> Behaviour;
     fn into_stats(self: Box<Self>, run_id: u64) -> Stats;
 
     fn as_provider_common(&self) -> &ProviderCommon;
@@ -106,24 +121,38 @@ where
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum State {
-    Idle,
-    Busy,
+// subtask being computed in one of the slots, together with the event which
+// concludes it
+#[derive(Debug, Serialize, Deserialize)]
+struct Assignment {
+    handle: EventHandle,
+    subtask: SubTask,
+    requestor_id: Id,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ProviderCommon {
     id: Id,
     min_price: f64,
     usage_factor: f64,
-    state: State,
+    capacity: usize,
+    assignments: Vec<Assignment>,
+    // crashes per second of computing; a crash loses the subtask silently
+    failure_rate: f64,
     profit_margin: f64,
     last_checkpoint: f64,
     revenue: f64,
+    // amounts billed for the subtasks computed and not yet paid for, keyed by
+    // subtask and requestor ids; a repeating task hands out the same subtask
+    // again, so the bills are settled in order
+    invoices: BTreeMap<(Id, Id), VecDeque<f64>>,
+    revenue_lost: f64,
     num_subtasks_assigned: usize,
     num_subtasks_computed: usize,
     num_subtasks_cancelled: usize,
+    num_subtasks_lost: usize,
+    num_subtasks_timed_out: usize,
+    num_subtasks_unpaid: usize,
 }
 
 impl ProviderCommon {
@@ -135,13 +164,20 @@ impl ProviderCommon {
             id,
             min_price,
             usage_factor,
-            state: State::Idle,
+            capacity: 1,
+            assignments: Vec::new(),
+            failure_rate: 0.0,
             profit_margin: 1.0,
             last_checkpoint: 0.0,
             revenue: 0.0,
+            invoices: BTreeMap::new(),
+            revenue_lost: 0.0,
             num_subtasks_assigned: 0,
             num_subtasks_computed: 0,
             num_subtasks_cancelled: 0,
+            num_subtasks_lost: 0,
+            num_subtasks_timed_out: 0,
+            num_subtasks_unpaid: 0,
         }
     }
 
@@ -153,7 +189,47 @@ impl ProviderCommon {
         self.usage_factor
     }
 
-    fn price(&self) -> f64 {
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        assert!(capacity > 0, "provider needs at least one slot");
+
+        self.capacity = capacity;
+    }
+
+    pub fn failure_rate(&self) -> f64 {
+        self.failure_rate
+    }
+
+    pub fn set_failure_rate(&mut self, failure_rate: f64) {
+        assert!(failure_rate >= 0.0, "failure rate cannot be negative");
+
+        self.failure_rate = failure_rate;
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.assignments.is_empty()
+    }
+
+    pub fn revenue(&self) -> f64 {
+        self.revenue
+    }
+
+    pub fn receivables(&self) -> f64 {
+        // summing up from 0.0 rather than -0.0 keeps empty sums positive
+        self.invoices
+            .values()
+            .flatten()
+            .fold(0.0, |total, amount| total + amount)
+    }
+
+    pub fn num_free_slots(&self) -> usize {
+        self.capacity - self.assignments.len()
+    }
+
+    pub fn price(&self) -> f64 {
         (1.0 + self.profit_margin) * self.min_price
     }
 
@@ -177,49 +253,100 @@ impl ProviderCommon {
         );
     }
 
+    // adapts the profit margin to the utilisation of the slots since the last
+    // checkpoint; the `excluded` slots are accounted neither as busy nor as idle
+    fn adapt_profit_margin(&mut self, now: f64, excluded: usize) {
+        let duration = now - self.last_checkpoint;
+        let capacity = self.capacity as f64;
+        let busy = self.assignments.len() - excluded;
+        let idle = self.capacity - self.assignments.len();
+
+        if busy > 0 {
+            self.increase_profit_margin(duration * busy as f64 / capacity);
+        }
+
+        if idle > 0 {
+            self.decrease_profit_margin(duration * idle as f64 / capacity);
+        }
+
+        self.last_checkpoint = now;
+    }
+
     pub fn send_benchmark(&self) -> f64 {
         self.usage_factor
     }
 
     pub fn send_offer(&mut self) -> Option<f64> {
-        match self.state {
-            State::Idle => Some(self.price()),
-            _ => None,
+        if self.num_free_slots() > 0 {
+            Some(self.price())
+        } else {
+            None
         }
     }
 
-    pub fn receive_subtask<Rng>(
+    pub fn receive_subtask<Rng, T>(
         &mut self,
-        engine: &mut Engine<Event>,
-        _rng: &mut Rng,
+        engine: &mut Engine<Event, T>,
+        rng: &mut Rng,
         subtask: &SubTask,
         requestor_id: Id,
         bid: f64,
     ) where
         Rng: rand::Rng,
+        T: Time,
     {
         debug!("P{}:received {} from R{}", self.id, subtask, requestor_id);
 
-        self.state = State::Busy;
         self.num_subtasks_assigned += 1;
-
-        self.decrease_profit_margin(engine.now() - self.last_checkpoint);
-        self.last_checkpoint = engine.now();
+        self.adapt_profit_margin(engine.now().as_secs(), 0);
 
         let expected_usage = subtask.nominal_usage * self.usage_factor;
-        if expected_usage * bid > subtask.budget {
+        let (delay, event) = if expected_usage * bid > subtask.budget {
             // schedule budget exceeded event
-            engine.schedule(
+            (
                 subtask.budget / bid,
                 Event::SubTaskBudgetExceeded(*subtask, requestor_id, self.id),
-            );
+            )
         } else {
             // schedule subtask computed event
-            engine.schedule(
+            (
                 expected_usage,
                 Event::SubTaskComputed(*subtask, requestor_id, self.id, bid),
-            );
-        }
+            )
+        };
+
+        // the provider may crash before getting that far
+        let crash = if self.failure_rate > 0.0 {
+            Some(Exp::new(self.failure_rate).sample(rng))
+        } else {
+            None
+        };
+
+        let handle = match crash {
+            Some(crash) if crash < delay => engine.schedule(
+                T::from_secs(crash),
+                Event::SubTaskDropped(*subtask, requestor_id, self.id),
+            ),
+            _ => engine.schedule(T::from_secs(delay), event),
+        };
+
+        self.assignments.push(Assignment {
+            handle,
+            subtask: *subtask,
+            requestor_id,
+        });
+    }
+
+    fn remove_assignment(&mut self, subtask: &SubTask, requestor_id: Id) {
+        let index = self
+            .assignments
+            .iter()
+            .position(|assignment| {
+                assignment.subtask == *subtask && assignment.requestor_id == requestor_id
+            })
+            .expect("assignment not found");
+
+        self.assignments.remove(index);
     }
 
     pub fn finish_computing(&mut self, now: f64, subtask: &SubTask, requestor_id: Id) {
@@ -228,11 +355,21 @@ impl ProviderCommon {
             self.id, subtask, requestor_id,
         );
 
-        self.state = State::Idle;
+        self.adapt_profit_margin(now, 0);
+        self.remove_assignment(subtask, requestor_id);
         self.num_subtasks_computed += 1;
+    }
 
-        self.increase_profit_margin(now - self.last_checkpoint);
-        self.last_checkpoint = now;
+    pub fn bill(&mut self, subtask: &SubTask, requestor_id: Id, amount: f64) {
+        debug!(
+            "P{}:billing R{} {} for {}",
+            self.id, requestor_id, amount, subtask
+        );
+
+        self.invoices
+            .entry((*subtask.id(), requestor_id))
+            .or_default()
+            .push_back(amount);
     }
 
     pub fn receive_payment(&mut self, subtask: &SubTask, requestor_id: Id, payment: Option<f64>) {
@@ -248,6 +385,47 @@ impl ProviderCommon {
                 self.id, requestor_id, subtask
             );
         }
+
+        self.settle_invoice(subtask, requestor_id, payment.unwrap_or(0.0));
+    }
+
+    // the requestor withdrew the subtask once computed, and pays nothing for it
+    pub fn receive_cancellation(&mut self, subtask: &SubTask, requestor_id: Id) {
+        debug!(
+            "P{}:{} cancelled by R{} after computing",
+            self.id, subtask, requestor_id
+        );
+
+        self.num_subtasks_cancelled += 1;
+        self.settle_invoice(subtask, requestor_id, 0.0);
+    }
+
+    // the requestor had given up on the subtask by the time the result arrived,
+    // and discarded it; nothing will ever be paid for it
+    pub fn write_off(&mut self, subtask: &SubTask, requestor_id: Id) {
+        debug!(
+            "P{}:{} discarded by R{}, writing it off",
+            self.id, subtask, requestor_id
+        );
+
+        self.settle_invoice(subtask, requestor_id, 0.0);
+    }
+
+    fn settle_invoice(&mut self, subtask: &SubTask, requestor_id: Id, paid: f64) {
+        let key = (*subtask.id(), requestor_id);
+        let billed = match self.invoices.get_mut(&key).and_then(VecDeque::pop_front) {
+            Some(billed) => billed,
+            None => return,
+        };
+
+        if self.invoices[&key].is_empty() {
+            self.invoices.remove(&key);
+        }
+
+        if paid < billed {
+            self.revenue_lost += billed - paid;
+            self.num_subtasks_unpaid += 1;
+        }
     }
 
     pub fn cancel_computing(&mut self, now: f64, subtask: &SubTask, requestor_id: Id) {
@@ -256,10 +434,74 @@ impl ProviderCommon {
             self.id, subtask, requestor_id
         );
 
-        self.state = State::Idle;
+        // time spent on a cancelled subtask earns nothing, yet the slot was
+        // not idle either
+        self.adapt_profit_margin(now, 1);
+        self.remove_assignment(subtask, requestor_id);
         self.num_subtasks_cancelled += 1;
+    }
 
-        self.last_checkpoint = now;
+    pub fn crash(&mut self, now: f64, subtask: &SubTask, requestor_id: Id) {
+        debug!(
+            "P{}:crashed while computing {} of R{}",
+            self.id, subtask, requestor_id
+        );
+
+        // the requestor is never told; it is up to them to notice the loss
+        self.adapt_profit_margin(now, 1);
+        self.remove_assignment(subtask, requestor_id);
+        self.num_subtasks_lost += 1;
+    }
+
+    // the requestor withdrew the subtask as its deadline has passed; nothing
+    // happens if the provider is not computing it anymore
+    pub fn time_out<T>(
+        &mut self,
+        engine: &mut Engine<Event, T>,
+        subtask: &SubTask,
+        requestor_id: Id,
+    ) where
+        T: Time,
+    {
+        let index = match self.assignments.iter().position(|assignment| {
+            assignment.subtask == *subtask && assignment.requestor_id == requestor_id
+        }) {
+            Some(index) => index,
+            None => return,
+        };
+
+        debug!(
+            "P{}:deadline of {} of R{} passed",
+            self.id, subtask, requestor_id
+        );
+
+        self.adapt_profit_margin(engine.now().as_secs(), 1);
+        engine.cancel(self.assignments.remove(index).handle);
+        self.num_subtasks_timed_out += 1;
+    }
+
+    // abandons the subtasks being computed so that the provider can leave the
+    // market; returns the subtasks and the requestors they belong to
+    pub fn leave<T>(&mut self, engine: &mut Engine<Event, T>) -> Vec<(SubTask, Id)>
+    where
+        T: Time,
+    {
+        debug!("P{}:leaving the market", self.id);
+
+        let mut abandoned = Vec::new();
+        for assignment in self.assignments.drain(..) {
+            engine.cancel(assignment.handle);
+            self.num_subtasks_cancelled += 1;
+
+            debug!(
+                "P{}:abandoned {} of R{}",
+                self.id, assignment.subtask, assignment.requestor_id
+            );
+
+            abandoned.push((assignment.subtask, assignment.requestor_id));
+        }
+
+        abandoned
     }
 }
 
@@ -270,46 +512,260 @@ impl fmt::Display for ProviderCommon {
             r"Id:                             {},
             Min price:                      {},
             Usage factor:                   {},
+            Capacity:                       {},
+            Failure rate:                   {},
             Profit margin:                  {},
             Price:                          {},
             Revenue:                        {},
+            Revenue lost:                   {},
+            Receivables:                    {},
             Number of subtasks assigned:    {},
             Number of subtasks cancelled:   {},
-            Nunber of subtasks computed:    {}",
+            Nunber of subtasks computed:    {},
+            Number of subtasks lost:        {},
+            Number of subtasks timed out:   {},
+            Number of subtasks unpaid:      {}",
             self.id,
             self.min_price,
             self.usage_factor,
+            self.capacity,
+            self.failure_rate,
             self.profit_margin,
             self.price(),
             self.revenue,
+            self.revenue_lost,
+            self.receivables(),
             self.num_subtasks_assigned,
             self.num_subtasks_cancelled,
             self.num_subtasks_computed,
+            self.num_subtasks_lost,
+            self.num_subtasks_timed_out,
+            self.num_subtasks_unpaid,
         )
     }
 }
 
+pub(crate) mod boxed {
+    use std::collections::BTreeMap;
+
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Error, Serialize, Serializer};
+    use serde_derive::{Deserialize, Serialize};
+
+    use super::*;
+
+    type Providers<Rng> = BTreeMap<Id, Box<dyn Provider<Rng = Rng>>>;
+
+    #[derive(Serialize)]
+    #[serde(bound = "")]
+    enum ProviderRef<'a, Rng>
+    where
+        Rng: rand::Rng + 'static,
+    {
+        Regular(&'a RegularProvider<Rng>),
+        LinearUsageInflation(&'a LinearUsageInflationProvider<Rng>),
+        UndercutBudget(&'a UndercutBudgetProvider<Rng>),
+    }
+
+    #[derive(Deserialize)]
+    #[serde(bound = "")]
+    enum ProviderOwned<Rng>
+    where
+        Rng: rand::Rng + 'static,
+    {
+        Regular(RegularProvider<Rng>),
+        LinearUsageInflation(LinearUsageInflationProvider<Rng>),
+        UndercutBudget(UndercutBudgetProvider<Rng>),
+    }
+
+    fn as_ref<Rng>(provider: &dyn Provider<Rng = Rng>) -> Option<ProviderRef<'_, Rng>>
+    where
+        Rng: rand::Rng + 'static,
+    {
+        let any = provider.as_any();
+
+        if let Some(p) = any.downcast_ref::<RegularProvider<Rng>>() {
+            Some(ProviderRef::Regular(p))
+        } else if let Some(p) = any.downcast_ref::<LinearUsageInflationProvider<Rng>>() {
+            Some(ProviderRef::LinearUsageInflation(p))
+        } else if let Some(p) = any.downcast_ref::<UndercutBudgetProvider<Rng>>() {
+            Some(ProviderRef::UndercutBudget(p))
+        } else {
+            None
+        }
+    }
+
+    pub fn serialize<Rng, S>(providers: &Providers<Rng>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        Rng: rand::Rng + 'static,
+        S: Serializer,
+    {
+        providers
+            .values()
+            .map(|provider| as_ref(provider.as_ref()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| S::Error::custom("unknown provider type"))?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, Rng, D>(deserializer: D) -> Result<Providers<Rng>, D::Error>
+    where
+        Rng: rand::Rng + 'static,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<ProviderOwned<Rng>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|provider| -> Box<dyn Provider<Rng = Rng>> {
+                match provider {
+                    ProviderOwned::Regular(p) => Box::new(p),
+                    ProviderOwned::LinearUsageInflation(p) => Box::new(p),
+                    ProviderOwned::UndercutBudget(p) => Box::new(p),
+                }
+            })
+            .map(|provider| (*provider.id(), provider))
+            .collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use rand::SeedableRng;
     use statrs::assert_almost_eq;
 
+    use crate::id::IdAllocator;
+    use crate::rng::SimRng;
+
     #[test]
     fn send_offer() {
-        let mut provider = ProviderCommon::new(Id::new(), 1.0, 1.0);
-        provider.state = State::Idle;
+        let mut ids = IdAllocator::new();
+        let mut engine: Engine<Event, f64> = Engine::new();
+        let mut rng = SimRng::seed_from_u64(42);
+        let mut provider = ProviderCommon::new(ids.allocate(), 1.0, 1.0);
 
         assert_eq!(provider.send_offer(), Some(2.0));
 
-        provider.state = State::Busy;
+        let subtask = SubTask::new(ids.allocate(), 1.0, 10.0);
+        provider.receive_subtask(&mut engine, &mut rng, &subtask, ids.allocate(), 2.0);
 
         assert_eq!(provider.send_offer(), None);
     }
 
+    #[test]
+    fn multiple_slots() {
+        let mut ids = IdAllocator::new();
+        let mut engine: Engine<Event, f64> = Engine::new();
+        let mut rng = SimRng::seed_from_u64(42);
+        let mut provider = ProviderCommon::new(ids.allocate(), 1.0, 1.0);
+        provider.set_capacity(4);
+
+        let requestor_id = ids.allocate();
+        let subtasks: Vec<SubTask> = (0..3)
+            .map(|_| SubTask::new(ids.allocate(), 1000.0, 1e4))
+            .collect();
+        for subtask in &subtasks {
+            provider.receive_subtask(&mut engine, &mut rng, subtask, requestor_id, 2.0);
+        }
+
+        assert_eq!(provider.num_free_slots(), 1);
+        assert_eq!(provider.send_offer(), Some(2.0));
+        assert_eq!(engine.len(), 3);
+
+        // three out of four slots busy for 1000s
+        provider.finish_computing(1000.0, &subtasks[0], requestor_id);
+
+        let expected = ((3.0 * ProviderCommon::BETA - ProviderCommon::ALPHA) * 250.0).exp();
+        assert_almost_eq!(provider.profit_margin, expected, 1e-9);
+        assert_eq!(provider.num_free_slots(), 2);
+
+        let abandoned = provider.leave(&mut engine);
+        assert_eq!(abandoned.len(), 2);
+        assert_eq!(provider.num_free_slots(), 4);
+        assert_eq!(provider.num_subtasks_cancelled, 2);
+
+        // only the event of the subtask finished above is left
+        match engine.pop() {
+            Some(Event::SubTaskComputed(subtask, ..)) => assert_eq!(subtask, subtasks[0]),
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert!(engine.pop().is_none());
+    }
+
+    #[test]
+    fn crash() {
+        let mut ids = IdAllocator::new();
+        let mut engine: Engine<Event, f64> = Engine::new();
+        let mut rng = SimRng::seed_from_u64(42);
+        let mut provider = ProviderCommon::new(ids.allocate(), 1.0, 1.0);
+        provider.set_failure_rate(1.0);
+
+        // a crash within a second is all but certain over 1e6s of computing
+        let requestor_id = ids.allocate();
+        let subtask = SubTask::new(ids.allocate(), 1e6, 1e8);
+        provider.receive_subtask(&mut engine, &mut rng, &subtask, requestor_id, 2.0);
+
+        let now = match engine.pop() {
+            Some(Event::SubTaskDropped(dropped, id, provider_id)) => {
+                assert_eq!(dropped, subtask);
+                assert_eq!(id, requestor_id);
+                assert_eq!(provider_id, provider.id);
+                engine.now()
+            }
+            event => panic!("unexpected event {:?}", event),
+        };
+
+        provider.crash(now, &subtask, requestor_id);
+
+        assert_eq!(provider.num_free_slots(), 1);
+        assert_eq!(provider.num_subtasks_lost, 1);
+        assert_eq!(provider.num_subtasks_computed, 0);
+    }
+
+    #[test]
+    fn revenue_lost() {
+        let mut ids = IdAllocator::new();
+        let mut provider = ProviderCommon::new(ids.allocate(), 1.0, 1.0);
+        let requestor_id = ids.allocate();
+        let subtasks: Vec<SubTask> = (0..3)
+            .map(|_| SubTask::new(ids.allocate(), 1.0, 1.0))
+            .collect();
+
+        for subtask in &subtasks {
+            provider.bill(subtask, requestor_id, 2.0);
+        }
+
+        provider.receive_payment(&subtasks[0], requestor_id, Some(2.0));
+        provider.receive_payment(&subtasks[1], requestor_id, Some(0.5));
+        provider.receive_cancellation(&subtasks[2], requestor_id);
+
+        assert_almost_eq!(provider.revenue, 2.5, 1e-9);
+        assert_almost_eq!(provider.revenue_lost, 3.5, 1e-9);
+        assert_eq!(provider.num_subtasks_unpaid, 2);
+        assert_eq!(provider.num_subtasks_cancelled, 1);
+        assert!(provider.invoices.is_empty());
+
+        // bills for a subtask computed more than once are settled in order
+        provider.bill(&subtasks[0], requestor_id, 1.0);
+        provider.bill(&subtasks[0], requestor_id, 3.0);
+        provider.receive_payment(&subtasks[0], requestor_id, Some(1.0));
+        provider.receive_payment(&subtasks[0], requestor_id, Some(3.0));
+        assert_almost_eq!(provider.revenue_lost, 3.5, 1e-9);
+        assert!(provider.invoices.is_empty());
+
+        // results discarded by the requestor are never paid for
+        provider.bill(&subtasks[1], requestor_id, 1.5);
+        assert_almost_eq!(provider.receivables(), 1.5, 1e-9);
+        provider.write_off(&subtasks[1], requestor_id);
+        assert_almost_eq!(provider.revenue_lost, 5.0, 1e-9);
+        assert_eq!(provider.num_subtasks_unpaid, 3);
+        assert!(provider.invoices.is_empty());
+    }
+
     #[test]
     fn increase_profit_margin() {
-        let mut provider = ProviderCommon::new(Id::new(), 1.0, 1.0);
+        let mut ids = IdAllocator::new();
+        let mut provider = ProviderCommon::new(ids.allocate(), 1.0, 1.0);
 
         assert_almost_eq!(provider.profit_margin, 1.0, 1e-5);
 
@@ -320,7 +776,8 @@ mod tests {
 
     #[test]
     fn decrease_profit_margin() {
-        let mut provider = ProviderCommon::new(Id::new(), 1.0, 1.0);
+        let mut ids = IdAllocator::new();
+        let mut provider = ProviderCommon::new(ids.allocate(), 1.0, 1.0);
 
         assert_almost_eq!(provider.profit_margin, 1.0, 1e-5);
 
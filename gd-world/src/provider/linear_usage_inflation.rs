@@ -7,7 +7,8 @@ use super::*;
 use crate::id::Id;
 use crate::task::SubTask;
 
-#[derive(Debug, DerefProviderCommon)]
+#[derive(Debug, Serialize, Deserialize, DerefProviderCommon)]
+#[serde(bound = "")]
 pub struct LinearUsageInflationProvider<Rng>
 where
     Rng: rand::Rng + 'static,
@@ -21,11 +22,7 @@ impl<Rng> LinearUsageInflationProvider<Rng>
 where
     Rng: rand::Rng + 'static,
 {
-    pub fn new(min_price: f64, usage_factor: f64, inflation_factor: f64) -> Self {
-        Self::with_id(Id::new(), min_price, usage_factor, inflation_factor)
-    }
-
-    pub fn with_id(id: Id, min_price: f64, usage_factor: f64, inflation_factor: f64) -> Self {
+    pub fn new(id: Id, min_price: f64, usage_factor: f64, inflation_factor: f64) -> Self {
         Self {
             inflation_factor,
             common: ProviderCommon::new(id, min_price, usage_factor),
@@ -63,10 +60,14 @@ where
         usage.min(subtask.budget / bid)
     }
 
+    fn behaviour(&self) -> Behaviour {
+        Behaviour::LinearUsageInflation
+    }
+
     fn into_stats(self: Box<Self>, run_id: u64) -> Stats {
         Stats {
             run_id,
-            behaviour: Behaviour::LinearUsageInflation,
+            behaviour: self.behaviour(),
             min_price: self.min_price,
             usage_factor: self.usage_factor,
             profit_margin: self.profit_margin,
@@ -75,6 +76,11 @@ where
             num_subtasks_assigned: self.num_subtasks_assigned,
             num_subtasks_computed: self.num_subtasks_computed,
             num_subtasks_cancelled: self.num_subtasks_cancelled,
+            num_subtasks_lost: self.num_subtasks_lost,
+            num_subtasks_timed_out: self.num_subtasks_timed_out,
+            revenue_lost: self.revenue_lost,
+            num_subtasks_unpaid: self.num_subtasks_unpaid,
+            receivables: self.receivables(),
         }
     }
 
@@ -101,13 +107,15 @@ mod tests {
 
     use statrs::assert_almost_eq;
 
+    use crate::id::IdAllocator;
     use crate::task::SubTask;
 
     #[test]
     fn report_usage() {
+        let mut ids = IdAllocator::new();
         let mut rng = rand::thread_rng();
-        let mut provider = LinearUsageInflationProvider::new(0.1, 0.5, 1.0);
-        let subtask = SubTask::new(100.0, 100.0);
+        let mut provider = LinearUsageInflationProvider::new(ids.allocate(), 0.1, 0.5, 1.0);
+        let subtask = SubTask::new(ids.allocate(), 100.0, 100.0);
         assert_almost_eq!(50.0, provider.report_usage(&mut rng, &subtask, 1.0), 1e-3);
 
         provider.num_subtasks_computed = 1;
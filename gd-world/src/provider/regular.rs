@@ -8,7 +8,8 @@ use rand::prelude::*;
 use super::*;
 use crate::task::SubTask;
 
-#[derive(Debug, DerefProviderCommon)]
+#[derive(Debug, Serialize, Deserialize, DerefProviderCommon)]
+#[serde(bound = "")]
 pub struct RegularProvider<Rng>
 where
     Rng: rand::Rng + 'static,
@@ -23,11 +24,7 @@ where
 {
     const USAGE_JITTER: f64 = 0.05;
 
-    pub fn new(min_price: f64, usage_factor: f64) -> Self {
-        Self::with_id(Id::new(), min_price, usage_factor)
-    }
-
-    pub fn with_id(id: Id, min_price: f64, usage_factor: f64) -> Self {
+    pub fn new(id: Id, min_price: f64, usage_factor: f64) -> Self {
         Self {
             common: ProviderCommon::new(id, min_price, usage_factor),
             phantom: PhantomData,
@@ -61,10 +58,14 @@ where
         usage * (1.0 + Normal::new(0.0, Self::USAGE_JITTER).sample(rng))
     }
 
+    fn behaviour(&self) -> Behaviour {
+        Behaviour::Regular
+    }
+
     fn into_stats(self: Box<Self>, run_id: u64) -> Stats {
         Stats {
             run_id,
-            behaviour: Behaviour::Regular,
+            behaviour: self.behaviour(),
             min_price: self.min_price,
             usage_factor: self.usage_factor,
             profit_margin: self.profit_margin,
@@ -73,6 +74,11 @@ where
             num_subtasks_assigned: self.num_subtasks_assigned,
             num_subtasks_computed: self.num_subtasks_computed,
             num_subtasks_cancelled: self.num_subtasks_cancelled,
+            num_subtasks_lost: self.num_subtasks_lost,
+            num_subtasks_timed_out: self.num_subtasks_timed_out,
+            revenue_lost: self.revenue_lost,
+            num_subtasks_unpaid: self.num_subtasks_unpaid,
+            receivables: self.receivables(),
         }
     }
 
@@ -97,12 +103,15 @@ where
 mod tests {
     use super::*;
 
+    use crate::id::IdAllocator;
     use crate::task::SubTask;
+
     #[test]
     fn report_usage() {
+        let mut ids = IdAllocator::new();
         let mut rng = rand::thread_rng();
-        let mut provider = RegularProvider::new(0.1, 0.25);
-        let subtask = SubTask::new(100.0, 100.0);
+        let mut provider = RegularProvider::new(ids.allocate(), 0.1, 0.25);
+        let subtask = SubTask::new(ids.allocate(), 100.0, 100.0);
 
         let check = |orig: f64, perturbed: f64| {
             let diff = (perturbed - orig).abs();
@@ -6,7 +6,8 @@ use gd_world_derive::DerefProviderCommon;
 use super::*;
 use crate::task::SubTask;
 
-#[derive(Debug, DerefProviderCommon)]
+#[derive(Debug, Serialize, Deserialize, DerefProviderCommon)]
+#[serde(bound = "")]
 pub struct UndercutBudgetProvider<Rng>
 where
     Rng: rand::Rng + 'static,
@@ -20,11 +21,7 @@ impl<Rng> UndercutBudgetProvider<Rng>
 where
     Rng: rand::Rng + 'static,
 {
-    pub fn new(min_price: f64, usage_factor: f64, epsilon: f64) -> Self {
-        Self::with_id(Id::new(), min_price, usage_factor, epsilon)
-    }
-
-    pub fn with_id(id: Id, min_price: f64, usage_factor: f64, epsilon: f64) -> Self {
+    pub fn new(id: Id, min_price: f64, usage_factor: f64, epsilon: f64) -> Self {
         Self {
             epsilon,
             common: ProviderCommon::new(id, min_price, usage_factor),
@@ -59,10 +56,14 @@ where
         subtask.budget / bid * (1.0 - self.epsilon)
     }
 
+    fn behaviour(&self) -> Behaviour {
+        Behaviour::UndercutBudget
+    }
+
     fn into_stats(self: Box<Self>, run_id: u64) -> Stats {
         Stats {
             run_id,
-            behaviour: Behaviour::UndercutBudget,
+            behaviour: self.behaviour(),
             min_price: self.min_price,
             usage_factor: self.usage_factor,
             profit_margin: self.profit_margin,
@@ -71,6 +72,11 @@ where
             num_subtasks_assigned: self.num_subtasks_assigned,
             num_subtasks_computed: self.num_subtasks_computed,
             num_subtasks_cancelled: self.num_subtasks_cancelled,
+            num_subtasks_lost: self.num_subtasks_lost,
+            num_subtasks_timed_out: self.num_subtasks_timed_out,
+            revenue_lost: self.revenue_lost,
+            num_subtasks_unpaid: self.num_subtasks_unpaid,
+            receivables: self.receivables(),
         }
     }
 
@@ -97,13 +103,15 @@ mod tests {
 
     use statrs::assert_almost_eq;
 
+    use crate::id::IdAllocator;
     use crate::task::SubTask;
 
     #[test]
     fn report_usage() {
+        let mut ids = IdAllocator::new();
         let mut rng = rand::thread_rng();
-        let mut provider = UndercutBudgetProvider::new(0.1, 0.1, 0.0);
-        let subtask = SubTask::new(100.0, 100.0);
+        let mut provider = UndercutBudgetProvider::new(ids.allocate(), 0.1, 0.1, 0.0);
+        let subtask = SubTask::new(ids.allocate(), 100.0, 100.0);
 
         assert_almost_eq!(provider.report_usage(&mut rng, &subtask, 1.0), 100.0, 1e-6);
         assert_almost_eq!(
@@ -4,19 +4,63 @@ mod task_queue;
 pub use self::defence::{DefenceMechanism, DefenceMechanismType, Redundancy};
 pub use self::task_queue::TaskQueue;
 
+use std::collections::BTreeMap;
 use std::fmt;
 
-use gd_engine::Engine;
+use gd_engine::{Engine, EventHandle, Time};
 use log::debug;
 use rand::distributions::Exp;
 use rand::prelude::*;
 use serde_derive::{Deserialize, Serialize};
 
-use crate::id::Id;
+use crate::id::{Id, IdAllocator};
+use crate::scenario::RequestorBehaviour;
 use crate::task::subtask;
 use crate::task::{SubTask, Task};
+use crate::trace::{DomainEvent, Outcome};
 use crate::world::Event;
 
+#[derive(
+    Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Behaviour {
+    #[default]
+    Honest,
+    NeverPay,
+    PayFraction,
+    PayLate,
+    CancelAfterCompute,
+}
+
+impl fmt::Display for Behaviour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Behaviour::Honest => "Honest",
+                Behaviour::NeverPay => "Never pay",
+                Behaviour::PayFraction => "Pay fraction",
+                Behaviour::PayLate => "Pay late",
+                Behaviour::CancelAfterCompute => "Cancel after compute",
+            }
+        )
+    }
+}
+
+impl From<RequestorBehaviour> for Behaviour {
+    fn from(behaviour: RequestorBehaviour) -> Self {
+        match behaviour {
+            RequestorBehaviour::Honest => Behaviour::Honest,
+            RequestorBehaviour::NeverPay => Behaviour::NeverPay,
+            RequestorBehaviour::PayFraction(_) => Behaviour::PayFraction,
+            RequestorBehaviour::PayLate(_) => Behaviour::PayLate,
+            RequestorBehaviour::CancelAfterCompute => Behaviour::CancelAfterCompute,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct Stats {
     pub run_id: u64,
@@ -28,15 +72,31 @@ pub struct Stats {
     pub num_readvertisements: usize,
     pub num_subtasks_computed: usize,
     pub num_subtasks_cancelled: usize,
+    #[serde(default)]
+    pub num_subtasks_timed_out: usize,
+    #[serde(default)]
+    pub num_subtasks_expired: usize,
+    #[serde(default)]
+    pub behaviour: Behaviour,
+    #[serde(default)]
+    pub fees_paid: f64,
+    #[serde(default)]
+    pub num_transactions: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Requestor {
     id: Id,
     max_price: f64,
     budget_factor: f64,
-    task: Option<Task>,
+    behaviour: RequestorBehaviour,
+    // tasks currently advertised, keyed by task id
+    tasks: BTreeMap<Id, Task>,
+    max_concurrent_tasks: usize,
+    // watchdogs of the subtasks sent out, keyed by subtask and provider ids
+    awaiting: BTreeMap<(Id, Id), EventHandle>,
     task_queue: TaskQueue,
+    #[serde(with = "defence::boxed")]
     defence_mechanism: Box<dyn DefenceMechanism>,
     mean_cost: (usize, f64),
     num_tasks_advertised: usize,
@@ -44,27 +104,31 @@ pub struct Requestor {
     num_readvertisements: usize,
     num_subtasks_computed: usize,
     num_subtasks_cancelled: usize,
+    num_subtasks_timed_out: usize,
+    num_subtasks_expired: usize,
+    fees_paid: f64,
+    num_transactions: usize,
+    // domain events not yet collected by the world
+    #[serde(skip)]
+    events: Vec<DomainEvent>,
 }
 
 impl Requestor {
     const MEAN_TASK_ARRIVAL_TIME: f64 = 3600.0;
     const READVERT_DELAY: f64 = 60.0;
+    // multiple of the longest time the provider may spend on a subtask after
+    // which the subtask is presumed lost
+    const OVERDUE_FACTOR: f64 = 2.0;
 
-    pub fn new(max_price: f64, budget_factor: f64, dm_type: DefenceMechanismType) -> Self {
-        Self::with_id(Id::new(), max_price, budget_factor, dm_type)
-    }
-
-    pub fn with_id(
-        id: Id,
-        max_price: f64,
-        budget_factor: f64,
-        dm_type: DefenceMechanismType,
-    ) -> Self {
+    pub fn new(id: Id, max_price: f64, budget_factor: f64, dm_type: DefenceMechanismType) -> Self {
         Self {
             id,
             max_price,
             budget_factor,
-            task: None,
+            behaviour: RequestorBehaviour::Honest,
+            tasks: BTreeMap::new(),
+            max_concurrent_tasks: 1,
+            awaiting: BTreeMap::new(),
             task_queue: TaskQueue::new(),
             defence_mechanism: dm_type.into_dm(id),
             mean_cost: (0, 0.0),
@@ -73,6 +137,11 @@ impl Requestor {
             num_readvertisements: 0,
             num_subtasks_computed: 0,
             num_subtasks_cancelled: 0,
+            num_subtasks_timed_out: 0,
+            num_subtasks_expired: 0,
+            fees_paid: 0.0,
+            num_transactions: 0,
+            events: Vec::new(),
         }
     }
 
@@ -88,6 +157,68 @@ impl Requestor {
         self.budget_factor
     }
 
+    pub fn behaviour(&self) -> RequestorBehaviour {
+        self.behaviour
+    }
+
+    pub fn set_behaviour(&mut self, behaviour: RequestorBehaviour) {
+        match behaviour {
+            RequestorBehaviour::PayFraction(fraction) => assert!(
+                (0.0..=1.0).contains(&fraction),
+                "fraction paid must lie within [0, 1]"
+            ),
+            RequestorBehaviour::PayLate(delay) => {
+                assert!(delay >= 0.0, "payment delay cannot be negative")
+            }
+            _ => {}
+        }
+
+        self.behaviour = behaviour;
+    }
+
+    // extra time the payment for a computed subtask is held back for
+    pub fn payment_delay(&self) -> f64 {
+        match self.behaviour {
+            RequestorBehaviour::PayLate(delay) => delay,
+            _ => 0.0,
+        }
+    }
+
+    pub fn cancels_after_compute(&self) -> bool {
+        self.behaviour == RequestorBehaviour::CancelAfterCompute
+    }
+
+    pub fn max_concurrent_tasks(&self) -> usize {
+        self.max_concurrent_tasks
+    }
+
+    pub fn set_max_concurrent_tasks(&mut self, max_concurrent_tasks: usize) {
+        assert!(
+            max_concurrent_tasks > 0,
+            "at least one task has to be allowed"
+        );
+
+        self.max_concurrent_tasks = max_concurrent_tasks;
+    }
+
+    pub fn has_task(&self, task_id: Id) -> bool {
+        self.tasks.contains_key(&task_id)
+    }
+
+    // whether any of the advertised tasks has subtasks yet to be handed out
+    pub fn is_waiting(&self) -> bool {
+        self.tasks.values().any(Task::is_pending)
+    }
+
+    // whether there are no tasks left, either queued or in progress
+    pub fn is_done(&self) -> bool {
+        self.task_queue.is_empty() && self.tasks.is_empty()
+    }
+
+    pub fn num_subtasks_in_flight(&self) -> usize {
+        self.awaiting.len()
+    }
+
     pub fn task_queue(&self) -> &TaskQueue {
         &self.task_queue
     }
@@ -96,21 +227,55 @@ impl Requestor {
         &mut self.task_queue
     }
 
-    pub fn advertise<Rng>(&mut self, engine: &mut Engine<Event>, rng: &mut Rng)
-    where
+    pub fn advertise<Rng, T>(
+        &mut self,
+        engine: &mut Engine<Event, T>,
+        ids: &mut IdAllocator,
+        rng: &mut Rng,
+    ) where
         Rng: rand::Rng,
+        T: Time,
     {
-        if let Some(task) = &self.task {
-            if task.is_pending() {
-                self.num_readvertisements += 1;
-                engine.schedule(Self::READVERT_DELAY, Event::TaskAdvertisement(self.id));
+        self.task_queue.drop_expired(engine.now().as_secs());
+
+        let pending: Vec<Id> = self
+            .tasks
+            .values()
+            .filter(|task| task.is_pending())
+            .map(|task| *task.id())
+            .collect();
+
+        for task_id in pending {
+            self.num_readvertisements += 1;
+
+            let event = Event::TaskAdvertisement(self.id, task_id);
+            engine.schedule_with_priority(
+                T::from_secs(Self::READVERT_DELAY),
+                event.priority(),
+                event,
+            );
+        }
+
+        while self.tasks.len() < self.max_concurrent_tasks {
+            let mut task = match self.task_queue.pop() {
+                Some(task) => task,
+                None => break,
+            };
+
+            // repeating queues hand out copies of the same task
+            if self.tasks.contains_key(task.id()) {
+                task = task.respawn(ids);
             }
-        } else if let Some(task) = self.task_queue.pop() {
-            self.task = Some(task);
+
+            let task_id = *task.id();
+            self.tasks.insert(task_id, task);
             self.num_tasks_advertised += 1;
-            engine.schedule(
-                Exp::new(1.0 / Self::MEAN_TASK_ARRIVAL_TIME).sample(rng),
-                Event::TaskAdvertisement(self.id),
+
+            let event = Event::TaskAdvertisement(self.id, task_id);
+            engine.schedule_with_priority(
+                T::from_secs(Exp::new(1.0 / Self::MEAN_TASK_ARRIVAL_TIME).sample(rng)),
+                event.priority(),
+                event,
             );
         }
     }
@@ -120,13 +285,90 @@ impl Requestor {
             .insert_provider_rating(provider_id, reported_usage)
     }
 
-    pub fn select_offers(&mut self, bids: Vec<(Id, f64)>) -> Vec<(Id, SubTask, f64)> {
-        // send available subtasks to eligible providers
-        let task = self.task.as_mut().expect("task not found");
+    pub fn select_offers(
+        &mut self,
+        now: f64,
+        task_id: Id,
+        bids: Vec<(Id, f64)>,
+    ) -> Vec<(Id, SubTask, f64)> {
+        let task = self.tasks.get_mut(&task_id).expect("task not found");
+
+        // subtasks past their absolute deadline are not handed out anymore
+        let expired = task.expire(now);
+        if expired > 0 {
+            debug!(
+                "R{}:{} subtasks of task {} expired",
+                self.id, expired, task_id
+            );
+
+            self.num_subtasks_expired += expired;
+        }
 
+        // send available subtasks to eligible providers
         self.defence_mechanism.assign_subtasks(task, bids)
     }
 
+    // starts waiting for the provider to report back on the subtask, either
+    // until its deadline or until it is presumed lost, whichever comes first;
+    // `latency` bounds the time the subtask and its result spend in transit
+    pub fn await_reply<T>(
+        &mut self,
+        engine: &mut Engine<Event, T>,
+        subtask: &SubTask,
+        provider_id: Id,
+        bid: f64,
+        latency: f64,
+    ) where
+        T: Time,
+    {
+        let overdue = (subtask.budget / bid + latency) * Self::OVERDUE_FACTOR;
+        let time_left = subtask
+            .deadline
+            .map(|deadline| deadline.time_left(engine.now().as_secs()));
+
+        let handle = match time_left {
+            Some(time_left) if time_left < overdue => engine.schedule(
+                T::from_secs(time_left),
+                Event::SubTaskTimedOut(*subtask, self.id, provider_id),
+            ),
+            _ => engine.schedule(
+                T::from_secs(overdue),
+                Event::SubTaskOverdue(*subtask, self.id, provider_id),
+            ),
+        };
+
+        self.awaiting.insert((*subtask.id(), provider_id), handle);
+    }
+
+    pub fn is_awaiting(&self, subtask: &SubTask, provider_id: Id) -> bool {
+        self.awaiting.contains_key(&(*subtask.id(), provider_id))
+    }
+
+    // returns false if the requestor has stopped waiting for the reply
+    pub fn receive_reply<T>(
+        &mut self,
+        engine: &mut Engine<Event, T>,
+        subtask: &SubTask,
+        provider_id: Id,
+    ) -> bool
+    where
+        T: Time,
+    {
+        match self.awaiting.remove(&(*subtask.id(), provider_id)) {
+            Some(handle) => {
+                engine.cancel(handle);
+                true
+            }
+            None => {
+                debug!(
+                    "R{}:ignoring late reply from P{} for {}",
+                    self.id, provider_id, subtask
+                );
+                false
+            }
+        }
+    }
+
     pub fn verify_subtask(
         &mut self,
         subtask: &SubTask,
@@ -135,28 +377,109 @@ impl Requestor {
     ) {
         debug!("R{}:verifying {}", self.id, subtask);
 
-        match self
+        let status = self
             .defence_mechanism
-            .verify_subtask(subtask, provider_id, reported_usage)
-        {
+            .verify_subtask(subtask, provider_id, reported_usage);
+
+        self.settle_subtask(subtask, provider_id, status);
+    }
+
+    fn settle_subtask(&mut self, subtask: &SubTask, provider_id: Id, status: subtask::Status) {
+        let task_id = subtask.task_id().expect("task not found");
+
+        // the defence mechanism may have updated ratings while verifying
+        self.events
+            .append(&mut self.defence_mechanism.take_events());
+        self.events.push(DomainEvent::Verified {
+            requestor_id: self.id,
+            provider_id,
+            subtask_id: *subtask.id(),
+            outcome: Outcome::from(&status),
+        });
+
+        match status {
             subtask::Status::Done => {
                 self.num_subtasks_computed += 1;
-                self.task
-                    .as_mut()
+                self.tasks
+                    .get_mut(&task_id)
                     .expect("task not found")
                     .push_done(*subtask);
             }
             subtask::Status::Cancelled => {
                 self.num_subtasks_cancelled += 1;
-                self.task
-                    .as_mut()
+                self.tasks
+                    .get_mut(&task_id)
                     .expect("task not found")
-                    .push_pending(*subtask);
+                    .requeue(*subtask);
             }
             subtask::Status::Pending => {}
         }
     }
 
+    pub fn notify_provider_left<T>(
+        &mut self,
+        engine: &mut Engine<Event, T>,
+        subtask: &SubTask,
+        provider_id: Id,
+    ) where
+        T: Time,
+    {
+        debug!(
+            "R{}:P{} left before computing {}",
+            self.id, provider_id, subtask
+        );
+
+        if self.receive_reply(engine, subtask, provider_id) {
+            self.verify_subtask(subtask, provider_id, None);
+        }
+    }
+
+    // the provider has not reported back in time; the subtask is treated as
+    // cancelled, and any reply arriving later on is ignored
+    pub fn notify_overdue(&mut self, subtask: &SubTask, provider_id: Id) -> bool {
+        if self
+            .awaiting
+            .remove(&(*subtask.id(), provider_id))
+            .is_none()
+        {
+            return false;
+        }
+
+        debug!(
+            "R{}:P{} presumed to have lost {}",
+            self.id, provider_id, subtask
+        );
+
+        self.verify_subtask(subtask, provider_id, None);
+        true
+    }
+
+    // the subtask's deadline has passed; the subtask is withdrawn from the
+    // provider and put back to pending
+    pub fn notify_timed_out(&mut self, subtask: &SubTask, provider_id: Id) -> bool {
+        if self
+            .awaiting
+            .remove(&(*subtask.id(), provider_id))
+            .is_none()
+        {
+            return false;
+        }
+
+        debug!(
+            "R{}:P{} missed the deadline of {}",
+            self.id, provider_id, subtask
+        );
+
+        self.num_subtasks_timed_out += 1;
+
+        let status = self
+            .defence_mechanism
+            .time_out_subtask(subtask, provider_id);
+
+        self.settle_subtask(subtask, provider_id, status);
+        true
+    }
+
     pub fn send_payment(
         &mut self,
         subtask: &SubTask,
@@ -181,22 +504,59 @@ impl Requestor {
             current_cost_wrt_budget * 100.0,
             *current_mean * 100.0,
         );
-        debug!(
-            "R{}:sending payment {} for {} to P{}",
-            self.id, payment, subtask, provider_id
-        );
+        let payment = match self.behaviour {
+            RequestorBehaviour::Honest | RequestorBehaviour::PayLate(_) => Some(payment),
+            RequestorBehaviour::PayFraction(fraction) => Some(payment * fraction),
+            RequestorBehaviour::NeverPay | RequestorBehaviour::CancelAfterCompute => None,
+        };
+
+        match payment {
+            Some(payment) => debug!(
+                "R{}:sending payment {} for {} to P{}",
+                self.id, payment, subtask, provider_id
+            ),
+            None => debug!(
+                "R{}:withholding payment for {} from P{}",
+                self.id, subtask, provider_id
+            ),
+        }
 
-        Some(payment)
+        payment
     }
 
-    pub fn complete_task(&mut self) {
-        if self.task.as_ref().expect("task not found").is_done() {
-            debug!("R{}:task computed", self.id);
+    // settles a batch of payments in a single transaction
+    pub fn pay_transaction_fee(&mut self, fee: f64) {
+        debug!("R{}:paying transaction fee {}", self.id, fee);
+
+        self.fees_paid += fee;
+        self.num_transactions += 1;
+    }
+
+    // removes the task once each of its subtasks has either been computed or
+    // expired; only the former counts as computed
+    pub fn complete_task(&mut self, task_id: Id) {
+        let task = self.tasks.get(&task_id).expect("task not found");
+        if !task.is_finished() {
+            return;
+        }
+
+        if task.is_done() {
+            debug!("R{}:task {} computed", self.id, task_id);
 
-            self.defence_mechanism.complete_task();
             self.num_tasks_computed += 1;
-            self.task = None;
+        } else {
+            debug!("R{}:task {} expired", self.id, task_id);
         }
+
+        self.defence_mechanism.complete_task();
+        self.events
+            .append(&mut self.defence_mechanism.take_events());
+
+        self.tasks.remove(&task_id);
+    }
+
+    pub fn take_events(&mut self) -> Vec<DomainEvent> {
+        std::mem::take(&mut self.events)
     }
 
     pub fn into_stats(self, run_id: u64) -> Stats {
@@ -210,6 +570,11 @@ impl Requestor {
             num_readvertisements: self.num_readvertisements,
             num_subtasks_computed: self.num_subtasks_computed,
             num_subtasks_cancelled: self.num_subtasks_cancelled,
+            num_subtasks_timed_out: self.num_subtasks_timed_out,
+            num_subtasks_expired: self.num_subtasks_expired,
+            behaviour: self.behaviour.into(),
+            fees_paid: self.fees_paid,
+            num_transactions: self.num_transactions,
         }
     }
 }
@@ -222,22 +587,32 @@ impl fmt::Display for Requestor {
             Id:                             {},
             Max price:                      {},
             Budget factor:                  {},
+            Behaviour:                      {},
             Mean cost wrt budget:           {},
             Number of tasks advertised:     {},
             Number of tasks computed:       {},
             Number of readvertisements:     {},
             Number of subtasks computed:    {},
             Number of subtasks cancelled:   {},
+            Number of subtasks timed out:   {},
+            Number of subtasks expired:     {},
+            Fees paid:                      {},
+            Number of transactions:         {},
             ",
             self.id,
             self.max_price,
             self.budget_factor,
+            Behaviour::from(self.behaviour),
             self.mean_cost.1 * 100.0,
             self.num_tasks_advertised,
             self.num_tasks_computed,
             self.num_readvertisements,
             self.num_subtasks_computed,
             self.num_subtasks_cancelled,
+            self.num_subtasks_timed_out,
+            self.num_subtasks_expired,
+            self.fees_paid,
+            self.num_transactions,
         )
     }
 }
@@ -246,31 +621,122 @@ impl fmt::Display for Requestor {
 mod tests {
     use super::*;
 
+    use rand::SeedableRng;
     use statrs::assert_almost_eq;
 
+    use crate::rng::SimRng;
+
     #[test]
     fn send_payment() {
-        let mut requestor = Requestor::new(1.0, 1.0, DefenceMechanismType::Redundancy);
-        let p1 = (SubTask::new(100.0, 100.0), Id::new(), 0.1, 50.0); // (subtask, provider_id, bid, usage)
+        let mut ids = IdAllocator::new();
+        let mut requestor =
+            Requestor::new(ids.allocate(), 1.0, 1.0, DefenceMechanismType::Redundancy);
+        let p1 = (
+            SubTask::new(ids.allocate(), 100.0, 100.0),
+            ids.allocate(),
+            0.1,
+            50.0,
+        ); // (subtask, provider_id, bid, usage)
 
         assert_eq!(requestor.send_payment(&p1.0, p1.1, p1.2, p1.3), Some(5.0));
 
         assert_eq!(requestor.mean_cost.0, 1);
         assert_almost_eq!(requestor.mean_cost.1, 0.05, 1e-5);
+
+        requestor.set_behaviour(RequestorBehaviour::PayFraction(0.5));
+        assert_eq!(requestor.send_payment(&p1.0, p1.1, p1.2, p1.3), Some(2.5));
+
+        requestor.set_behaviour(RequestorBehaviour::PayLate(60.0));
+        assert_eq!(requestor.send_payment(&p1.0, p1.1, p1.2, p1.3), Some(5.0));
+        assert_almost_eq!(requestor.payment_delay(), 60.0, 1e-9);
+
+        for &behaviour in &[
+            RequestorBehaviour::NeverPay,
+            RequestorBehaviour::CancelAfterCompute,
+        ] {
+            requestor.set_behaviour(behaviour);
+            assert_eq!(requestor.send_payment(&p1.0, p1.1, p1.2, p1.3), None);
+        }
+        assert!(requestor.cancels_after_compute());
+
+        // the cost incurred is what was due, whatever was paid
+        assert_eq!(requestor.mean_cost.0, 5);
+        assert_almost_eq!(requestor.mean_cost.1, 0.05, 1e-5);
     }
 
     #[test]
     fn complete_task() {
-        let mut requestor = Requestor::new(1.0, 1.0, DefenceMechanismType::Redundancy);
-        let task = Task::new();
+        let mut ids = IdAllocator::new();
+        let mut requestor =
+            Requestor::new(ids.allocate(), 1.0, 1.0, DefenceMechanismType::Redundancy);
+        let task = Task::new(ids.allocate());
         requestor.task_queue.push(task.clone());
-        requestor.task = requestor.task_queue.pop();
+        requestor
+            .tasks
+            .insert(*task.id(), requestor.task_queue.pop().unwrap());
+
+        assert_eq!(requestor.tasks.get(task.id()), Some(&task));
+        assert!(requestor.tasks[task.id()].is_done());
 
-        assert_eq!(requestor.task, Some(task));
-        assert!(requestor.task.as_ref().unwrap().is_done());
+        requestor.complete_task(*task.id());
+
+        assert!(requestor.tasks.is_empty());
+    }
+
+    #[test]
+    fn await_reply() {
+        let mut ids = IdAllocator::new();
+        let mut engine: Engine<Event, f64> = Engine::new();
+        let mut requestor = Requestor::new(ids.allocate(), 1.0, 1.0, DefenceMechanismType::CTasks);
+        let subtask = SubTask::new(ids.allocate(), 100.0, 100.0);
+        let provider_id = ids.allocate();
+
+        // the time in transit counts towards the reply being overdue
+        requestor.await_reply(&mut engine, &subtask, provider_id, 0.1, 50.0);
+        let e = engine.peek().unwrap();
+        assert_almost_eq!(e.time(), 2100.0, 1e-9);
+        assert_eq!(e.event().kind(), "subtask_overdue");
+    }
+
+    #[test]
+    fn concurrent_tasks() {
+        let mut ids = IdAllocator::new();
+        let mut engine: Engine<Event, f64> = Engine::new();
+        let mut rng = SimRng::seed_from_u64(42);
+
+        let mut requestor = Requestor::new(ids.allocate(), 1.0, 1.0, DefenceMechanismType::CTasks);
+        requestor.set_max_concurrent_tasks(3);
+
+        let provider_id = ids.allocate();
+        requestor.receive_benchmark(provider_id, 1.0);
+
+        let mut task = Task::new(ids.allocate());
+        task.push_pending(SubTask::new(ids.allocate(), 100.0, 100.0));
+        requestor.task_queue.push(task);
+
+        // the single repeating task is copied into each free slot
+        requestor.advertise(&mut engine, &mut ids, &mut rng);
+        assert_eq!(requestor.tasks.len(), 3);
+        assert_eq!(requestor.num_tasks_advertised, 3);
+        assert_eq!(engine.len(), 3);
+
+        let task_ids: Vec<Id> = requestor.tasks.keys().cloned().collect();
+        let mut subtasks = Vec::new();
+        for &task_id in &task_ids {
+            let offers = requestor.select_offers(0.0, task_id, vec![(provider_id, 0.1)]);
+            assert_eq!(offers.len(), 1);
+            assert_eq!(offers[0].1.task_id(), Some(task_id));
+            subtasks.push(offers[0].1);
+        }
 
-        requestor.complete_task();
+        // results are routed back to the task they belong to
+        requestor.verify_subtask(&subtasks[1], provider_id, Some(100.0));
+        requestor.complete_task(task_ids[0]);
+        requestor.complete_task(task_ids[1]);
 
-        assert_eq!(requestor.task, None);
+        assert!(requestor.has_task(task_ids[0]));
+        assert!(!requestor.has_task(task_ids[1]));
+        assert!(requestor.has_task(task_ids[2]));
+        assert_eq!(requestor.num_tasks_computed, 1);
     }
 }
@@ -6,20 +6,22 @@ pub use self::ctasks::CTasks;
 pub use self::lgrola::LGRola;
 pub use self::redundancy::Redundancy;
 
+use std::any::Any;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::ops::{Deref, DerefMut};
 
 use log::{debug, warn};
 use num_traits::{NumAssign, NumCast};
-use serde_derive::Deserialize;
+use serde_derive::{Deserialize, Serialize};
 
 use crate::id::Id;
 use crate::task::subtask;
 use crate::task::{SubTask, Task};
+use crate::trace::DomainEvent;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 enum BanDuration<T>
 where
     T: fmt::Debug + NumAssign + NumCast,
@@ -48,7 +50,7 @@ where
     }
 }
 
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum DefenceMechanismType {
     LGRola,
     CTasks,
@@ -76,10 +78,17 @@ pub trait DefenceMechanism: fmt::Debug {
         reported_usage: Option<f64>,
     ) -> subtask::Status;
 
+    // the provider failed to report back on the subtask by its deadline
+    fn time_out_subtask(&mut self, subtask: &SubTask, provider_id: Id) -> subtask::Status {
+        self.verify_subtask(subtask, provider_id, None)
+    }
+
     fn complete_task(&mut self);
 
     fn as_dm_common(&self) -> &DefenceMechanismCommon;
     fn as_dm_common_mut(&mut self) -> &mut DefenceMechanismCommon;
+
+    fn as_any(&self) -> &dyn Any;
 }
 
 impl Deref for DefenceMechanism {
@@ -96,11 +105,14 @@ impl DerefMut for DefenceMechanism {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct DefenceMechanismCommon {
     requestor_id: Id,
-    ratings: HashMap<Id, f64>,
-    blacklisted_set: HashMap<Id, BanDuration<i64>>,
+    ratings: BTreeMap<Id, f64>,
+    blacklisted_set: BTreeMap<Id, BanDuration<i64>>,
+    // rating updates and bans not yet collected by the requestor
+    #[serde(skip)]
+    events: Vec<DomainEvent>,
 }
 
 impl DefenceMechanismCommon {
@@ -109,8 +121,9 @@ impl DefenceMechanismCommon {
     fn new(requestor_id: Id) -> Self {
         Self {
             requestor_id,
-            ratings: HashMap::new(),
-            blacklisted_set: HashMap::new(),
+            ratings: BTreeMap::new(),
+            blacklisted_set: BTreeMap::new(),
+            events: Vec::new(),
         }
     }
 
@@ -134,14 +147,37 @@ impl DefenceMechanismCommon {
             .expect("rating not found");
         *rating = new_rating;
 
-        if *rating >= Self::MAX_RATING {
+        self.events.push(DomainEvent::RatingUpdated {
+            requestor_id: self.requestor_id,
+            provider_id,
+            rating: new_rating,
+        });
+
+        if new_rating >= Self::MAX_RATING {
             debug!("R{}:P{} blacklisted", self.requestor_id, provider_id);
 
-            self.blacklisted_set
-                .insert(provider_id, BanDuration::Indefinitely);
+            self.blacklist(provider_id, BanDuration::Indefinitely);
         }
     }
 
+    fn blacklist(&mut self, provider_id: Id, until: BanDuration<i64>) {
+        let requests = match until {
+            BanDuration::Until(requests) => Some(requests),
+            BanDuration::Indefinitely => None,
+        };
+
+        self.blacklisted_set.insert(provider_id, until);
+        self.events.push(DomainEvent::Blacklisted {
+            requestor_id: self.requestor_id,
+            provider_id,
+            requests,
+        });
+    }
+
+    pub fn take_events(&mut self) -> Vec<DomainEvent> {
+        std::mem::take(&mut self.events)
+    }
+
     fn filter_offers(&self, bids: Vec<(Id, f64)>) -> Vec<(Id, f64)> {
         debug!(
             "R{}:offers before filtering = {{ {} }}",
@@ -188,15 +224,69 @@ impl DefenceMechanismCommon {
     }
 }
 
+pub(crate) mod boxed {
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Error, Serialize, Serializer};
+    use serde_derive::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Serialize)]
+    enum DefenceMechanismRef<'a> {
+        LGRola(&'a LGRola),
+        CTasks(&'a CTasks),
+        Redundancy(&'a Redundancy),
+    }
+
+    #[derive(Deserialize)]
+    enum DefenceMechanismOwned {
+        LGRola(LGRola),
+        CTasks(CTasks),
+        Redundancy(Redundancy),
+    }
+
+    #[allow(clippy::borrowed_box)]
+    pub fn serialize<S>(dm: &Box<dyn DefenceMechanism>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let any = dm.as_any();
+
+        if let Some(dm) = any.downcast_ref::<LGRola>() {
+            DefenceMechanismRef::LGRola(dm).serialize(serializer)
+        } else if let Some(dm) = any.downcast_ref::<CTasks>() {
+            DefenceMechanismRef::CTasks(dm).serialize(serializer)
+        } else if let Some(dm) = any.downcast_ref::<Redundancy>() {
+            DefenceMechanismRef::Redundancy(dm).serialize(serializer)
+        } else {
+            Err(S::Error::custom("unknown defence mechanism"))
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Box<dyn DefenceMechanism>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match DefenceMechanismOwned::deserialize(deserializer)? {
+            DefenceMechanismOwned::LGRola(dm) => Box::new(dm),
+            DefenceMechanismOwned::CTasks(dm) => Box::new(dm),
+            DefenceMechanismOwned::Redundancy(dm) => Box::new(dm),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::id::IdAllocator;
+
     #[test]
     fn rank_offers() {
-        let mut dm = DefenceMechanismCommon::new(Id::new());
-        let bid1 = (Id::new(), 2.5); // (provider_id, bid/offer)
-        let bid2 = (Id::new(), 0.5);
+        let mut ids = IdAllocator::new();
+        let mut dm = DefenceMechanismCommon::new(ids.allocate());
+        let bid1 = (ids.allocate(), 2.5); // (provider_id, bid/offer)
+        let bid2 = (ids.allocate(), 0.5);
         dm.ratings.insert(bid1.0, 0.25);
         dm.ratings.insert(bid2.0, 0.75);
 
@@ -205,9 +295,10 @@ mod tests {
 
     #[test]
     fn filter_offers() {
-        let mut dm = DefenceMechanismCommon::new(Id::new());
-        let bid1 = (Id::new(), 1.0); // (provider_id, bid/offer)
-        let bid2 = (Id::new(), 2.0);
+        let mut ids = IdAllocator::new();
+        let mut dm = DefenceMechanismCommon::new(ids.allocate());
+        let bid1 = (ids.allocate(), 1.0); // (provider_id, bid/offer)
+        let bid2 = (ids.allocate(), 2.0);
 
         assert!(dm.blacklisted_set.is_empty());
         assert_eq!(dm.filter_offers(vec![bid1, bid2]), vec![bid1, bid2]);
@@ -220,8 +311,9 @@ mod tests {
 
     #[test]
     fn update_provider_rating() {
-        let mut dm = DefenceMechanismCommon::new(Id::new());
-        let provider = (Id::new(), 0.25);
+        let mut ids = IdAllocator::new();
+        let mut dm = DefenceMechanismCommon::new(ids.allocate());
+        let provider = (ids.allocate(), 0.25);
 
         dm.ratings.insert(provider.0, provider.1);
         dm.update_provider_rating(provider.0, 2.0);
@@ -1,7 +1,9 @@
-use std::collections::{HashMap, HashSet};
+use std::any::Any;
+use std::collections::{BTreeMap, HashSet};
 
 use gd_world_derive::DerefDefenceMechanismCommon;
 use log::debug;
+use serde_derive::{Deserialize, Serialize};
 use statrs::statistics::Statistics;
 
 use super::{DefenceMechanism, DefenceMechanismCommon};
@@ -10,17 +12,17 @@ use crate::id::Id;
 use crate::task::subtask;
 use crate::task::{SubTask, Task};
 
-#[derive(Debug, DerefDefenceMechanismCommon)]
+#[derive(Debug, Serialize, Deserialize, DerefDefenceMechanismCommon)]
 pub struct CTasks {
     common: DefenceMechanismCommon,
-    task_usages: HashMap<Id, Vec<f64>>,
+    task_usages: BTreeMap<Id, Vec<f64>>,
 }
 
 impl CTasks {
     pub fn new(id: Id) -> Self {
         Self {
             common: DefenceMechanismCommon::new(id),
-            task_usages: HashMap::new(),
+            task_usages: BTreeMap::new(),
         }
     }
 }
@@ -72,7 +74,7 @@ impl DefenceMechanism for CTasks {
     fn complete_task(&mut self) {
         let ids: HashSet<Id> = self.task_usages.iter().map(|(&id, _)| id).collect();
 
-        let task_usages: HashMap<Id, f64> = self
+        let task_usages: BTreeMap<Id, f64> = self
             .task_usages
             .iter()
             .map(|(&id, usages)| (id, usages.iter().geometric_mean()))
@@ -117,6 +119,10 @@ impl DefenceMechanism for CTasks {
     fn as_dm_common_mut(&mut self) -> &mut DefenceMechanismCommon {
         &mut self.common
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 
 #[cfg(test)]
@@ -125,16 +131,19 @@ mod tests {
 
     use statrs::assert_almost_eq;
 
+    use crate::id::IdAllocator;
+
     #[test]
     fn assign_subtasks() {
-        let mut ctasks = CTasks::new(Id::new());
-        let s1 = SubTask::new(1.0, 1.0);
-        let s2 = SubTask::new(1.0, 1.0);
-        let mut task = Task::new();
+        let mut ids = IdAllocator::new();
+        let mut ctasks = CTasks::new(ids.allocate());
+        let s1 = SubTask::new(ids.allocate(), 1.0, 1.0);
+        let s2 = SubTask::new(ids.allocate(), 1.0, 1.0);
+        let mut task = Task::new(ids.allocate());
         task.push_pending(s1);
         task.push_pending(s2);
-        let bid1 = (Id::new(), 1.0);
-        let bid2 = (Id::new(), 2.0);
+        let bid1 = (ids.allocate(), 1.0);
+        let bid2 = (ids.allocate(), 2.0);
         ctasks.ratings.insert(bid1.0, 1.0);
         ctasks.ratings.insert(bid2.0, 1.0);
 
@@ -146,11 +155,12 @@ mod tests {
 
     #[test]
     fn complete_task() {
-        let mut ctasks = CTasks::new(Id::new());
+        let mut ids = IdAllocator::new();
+        let mut ctasks = CTasks::new(ids.allocate());
 
-        let id1 = Id::new();
-        let id2 = Id::new();
-        let id3 = Id::new();
+        let id1 = ids.allocate();
+        let id2 = ids.allocate();
+        let id3 = ids.allocate();
 
         ctasks.ratings.insert(id1, 0.5);
         ctasks.ratings.insert(id2, 0.1);
@@ -174,5 +184,4 @@ mod tests {
         assert_almost_eq!(ctasks.ratings[&id2], 0.5869, 1e-3);
         assert_almost_eq!(ctasks.ratings[&id3], 0.3097, 1e-3);
     }
-
 }
@@ -1,7 +1,9 @@
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::BTreeMap;
 
 use gd_world_derive::DerefDefenceMechanismCommon;
 use log::debug;
+use serde_derive::{Deserialize, Serialize};
 use statrs::statistics::{OrderStatistics, Statistics};
 
 use super::{BanDuration, DefenceMechanism, DefenceMechanismCommon};
@@ -10,19 +12,19 @@ use crate::id::Id;
 use crate::task::subtask;
 use crate::task::{SubTask, Task};
 
-#[derive(Debug, DerefDefenceMechanismCommon)]
+#[derive(Debug, Serialize, Deserialize, DerefDefenceMechanismCommon)]
 pub struct LGRola {
     common: DefenceMechanismCommon,
-    task_usages: HashMap<Id, Vec<f64>>,
-    collisions: HashMap<Id, u32>,
+    task_usages: BTreeMap<Id, Vec<f64>>,
+    collisions: BTreeMap<Id, u32>,
 }
 
 impl LGRola {
     pub fn new(id: Id) -> Self {
         Self {
             common: DefenceMechanismCommon::new(id),
-            task_usages: HashMap::new(),
-            collisions: HashMap::new(),
+            task_usages: BTreeMap::new(),
+            collisions: BTreeMap::new(),
         }
     }
 }
@@ -113,7 +115,7 @@ impl DefenceMechanism for LGRola {
                     requestor_id, id, until, usage, *collisions
                 );
 
-                self.blacklisted_set.insert(id, until);
+                self.blacklist(id, until);
             } else {
                 self.collisions
                     .entry(id)
@@ -154,22 +156,29 @@ impl DefenceMechanism for LGRola {
     fn as_dm_common_mut(&mut self) -> &mut DefenceMechanismCommon {
         &mut self.common
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::id::IdAllocator;
+
     #[test]
     fn assign_subtasks() {
-        let mut lgrola = LGRola::new(Id::new());
-        let s1 = SubTask::new(1.0, 1.0);
-        let s2 = SubTask::new(1.0, 1.0);
-        let mut task = Task::new();
+        let mut ids = IdAllocator::new();
+        let mut lgrola = LGRola::new(ids.allocate());
+        let s1 = SubTask::new(ids.allocate(), 1.0, 1.0);
+        let s2 = SubTask::new(ids.allocate(), 1.0, 1.0);
+        let mut task = Task::new(ids.allocate());
         task.push_pending(s1);
         task.push_pending(s2);
-        let bid1 = (Id::new(), 1.0);
-        let bid2 = (Id::new(), 2.0);
+        let bid1 = (ids.allocate(), 1.0);
+        let bid2 = (ids.allocate(), 2.0);
         lgrola.ratings.insert(bid1.0, 1.0);
         lgrola.ratings.insert(bid2.0, 1.0);
 
@@ -181,10 +190,11 @@ mod tests {
 
     #[test]
     fn complete_task() {
-        let mut lgrola = LGRola::new(Id::new());
+        let mut ids = IdAllocator::new();
+        let mut lgrola = LGRola::new(ids.allocate());
 
         for _ in 0..25 {
-            let id = Id::new();
+            let id = ids.allocate();
             lgrola.ratings.insert(id, 0.75);
             lgrola
                 .task_usages
@@ -193,7 +203,7 @@ mod tests {
                 .push(50.0);
         }
 
-        let outlier = (Id::new(), 0.1);
+        let outlier = (ids.allocate(), 0.1);
         lgrola.ratings.insert(outlier.0, outlier.1);
         lgrola.task_usages.insert(outlier.0, vec![2000.0]);
 
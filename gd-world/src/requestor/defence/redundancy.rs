@@ -1,9 +1,11 @@
 use super::{DefenceMechanism, DefenceMechanismCommon};
 
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::BTreeMap;
 
 use gd_world_derive::DerefDefenceMechanismCommon;
 use log::debug;
+use serde_derive::{Deserialize, Serialize};
 
 use crate::id::Id;
 use crate::task::subtask;
@@ -13,15 +15,15 @@ type VerificationResult = Option<(Id, f64)>;
 
 const REDUNDANCY_FACTOR: usize = 2;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 struct VerificationMap {
-    map: HashMap<Id, Vec<VerificationResult>>,
+    map: BTreeMap<Id, Vec<VerificationResult>>,
 }
 
 impl VerificationMap {
     fn new() -> Self {
         Self {
-            map: HashMap::new(),
+            map: BTreeMap::new(),
         }
     }
 
@@ -51,7 +53,7 @@ impl VerificationMap {
     }
 }
 
-#[derive(Debug, DerefDefenceMechanismCommon)]
+#[derive(Debug, Serialize, Deserialize, DerefDefenceMechanismCommon)]
 pub struct Redundancy {
     common: DefenceMechanismCommon,
     verification_map: VerificationMap,
@@ -91,14 +93,30 @@ impl DefenceMechanism for Redundancy {
         bids: Vec<(Id, f64)>,
     ) -> Vec<(Id, SubTask, f64)> {
         let bids = self.filter_offers(bids);
-        let bids = self.rank_offers(bids);
+        let mut bids = self.rank_offers(bids);
 
         let mut messages: Vec<(Id, SubTask, f64)> = Vec::new();
 
-        for chunk in bids.chunks_exact(REDUNDANCY_FACTOR) {
+        loop {
+            // a provider with several free slots offers each of them, yet the
+            // replicas of a subtask have to go to distinct providers
+            let mut chunk: Vec<(Id, f64)> = Vec::with_capacity(REDUNDANCY_FACTOR);
+            let mut i = 0;
+            while chunk.len() < REDUNDANCY_FACTOR && i < bids.len() {
+                if chunk.iter().any(|&(id, _)| id == bids[i].0) {
+                    i += 1;
+                } else {
+                    chunk.push(bids.remove(i));
+                }
+            }
+
+            if chunk.len() < REDUNDANCY_FACTOR {
+                break;
+            }
+
             match task.pop_pending() {
                 Some(subtask) => {
-                    for &(provider_id, bid) in chunk {
+                    for (provider_id, bid) in chunk {
                         debug!("sending {} to P{} for {}", subtask, provider_id, bid);
 
                         messages.push((provider_id, subtask, bid));
@@ -156,6 +174,10 @@ impl DefenceMechanism for Redundancy {
     fn as_dm_common_mut(&mut self) -> &mut DefenceMechanismCommon {
         &mut self.common
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 
 #[cfg(test)]
@@ -164,14 +186,17 @@ mod tests {
 
     use statrs::assert_almost_eq;
 
+    use crate::id::IdAllocator;
+
     #[test]
     fn insert_verification() {
+        let mut ids = IdAllocator::new();
         let mut vmap = VerificationMap::new();
-        let id = Id::new();
+        let id = ids.allocate();
         vmap.insert_key(id);
 
-        let id1 = Id::new();
-        let id2 = Id::new();
+        let id1 = ids.allocate();
+        let id2 = ids.allocate();
 
         assert_eq!(vmap.insert_verification(id, Some((id1, 1.0))), None);
         assert_eq!(
@@ -204,9 +229,10 @@ mod tests {
 
     #[test]
     fn update_rating() {
-        let mut redundancy = Redundancy::new(Id::new());
-        let p1 = (Id::new(), 0.25, 25.0); // (provider_id, rating, usage)
-        let p2 = (Id::new(), 0.75, 75.0);
+        let mut ids = IdAllocator::new();
+        let mut redundancy = Redundancy::new(ids.allocate());
+        let p1 = (ids.allocate(), 0.25, 25.0); // (provider_id, rating, usage)
+        let p2 = (ids.allocate(), 0.75, 75.0);
         redundancy.ratings.insert(p1.0, p1.1);
         redundancy.ratings.insert(p2.0, p2.1);
 
@@ -231,12 +257,13 @@ mod tests {
 
     #[test]
     fn assign_subtasks() {
-        let mut redundancy = Redundancy::new(Id::new());
-        let subtask = SubTask::new(1.0, 1.0);
-        let mut task = Task::new();
+        let mut ids = IdAllocator::new();
+        let mut redundancy = Redundancy::new(ids.allocate());
+        let subtask = SubTask::new(ids.allocate(), 1.0, 1.0);
+        let mut task = Task::new(ids.allocate());
         task.push_pending(subtask);
-        let bid1 = (Id::new(), 1.0);
-        let bid2 = (Id::new(), 2.0);
+        let bid1 = (ids.allocate(), 1.0);
+        let bid2 = (ids.allocate(), 2.0);
         redundancy.ratings.insert(bid1.0, 1.0);
         redundancy.ratings.insert(bid2.0, 1.0);
 
@@ -246,16 +273,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn assign_subtasks_to_distinct_providers() {
+        let mut ids = IdAllocator::new();
+        let mut redundancy = Redundancy::new(ids.allocate());
+        let subtask = SubTask::new(ids.allocate(), 1.0, 1.0);
+        let mut task = Task::new(ids.allocate());
+        task.push_pending(subtask);
+        task.push_pending(SubTask::new(ids.allocate(), 1.0, 1.0));
+        let bid1 = (ids.allocate(), 1.0);
+        let bid2 = (ids.allocate(), 2.0);
+        redundancy.ratings.insert(bid1.0, 1.0);
+        redundancy.ratings.insert(bid2.0, 1.0);
+
+        // the provider with two free slots gets a single replica
+        assert_eq!(
+            redundancy.assign_subtasks(&mut task, vec![bid1, bid1, bid2]),
+            vec![(bid1.0, subtask, 1.0), (bid2.0, subtask, 2.0)]
+        );
+        assert!(task.is_pending());
+    }
+
     #[test]
     fn verify_subtask_successful() {
-        let mut redundancy = Redundancy::new(Id::new());
+        let mut ids = IdAllocator::new();
+        let mut redundancy = Redundancy::new(ids.allocate());
 
-        let p1 = (Id::new(), 0.25, 100.0);
-        let p2 = (Id::new(), 0.75, 75.0);
+        let p1 = (ids.allocate(), 0.25, 100.0);
+        let p2 = (ids.allocate(), 0.75, 75.0);
         redundancy.ratings.insert(p1.0, p1.1);
         redundancy.ratings.insert(p2.0, p2.1);
 
-        let subtask = SubTask::new(100.0, 100.0);
+        let subtask = SubTask::new(ids.allocate(), 100.0, 100.0);
         redundancy.verification_map.insert_key(*subtask.id());
 
         assert_eq!(
@@ -270,15 +319,16 @@ mod tests {
 
     #[test]
     fn verify_subtask_partial_success() {
+        let mut ids = IdAllocator::new();
         {
-            let mut redundancy = Redundancy::new(Id::new());
+            let mut redundancy = Redundancy::new(ids.allocate());
 
-            let p1 = (Id::new(), 0.25, 100.0);
-            let p2 = (Id::new(), 0.75, 75.0);
+            let p1 = (ids.allocate(), 0.25, 100.0);
+            let p2 = (ids.allocate(), 0.75, 75.0);
             redundancy.ratings.insert(p1.0, p1.1);
             redundancy.ratings.insert(p2.0, p2.1);
 
-            let subtask = SubTask::new(100.0, 100.0);
+            let subtask = SubTask::new(ids.allocate(), 100.0, 100.0);
             redundancy.verification_map.insert_key(*subtask.id());
 
             assert_eq!(
@@ -292,14 +342,14 @@ mod tests {
         }
 
         {
-            let mut redundancy = Redundancy::new(Id::new());
+            let mut redundancy = Redundancy::new(ids.allocate());
 
-            let p1 = (Id::new(), 0.25, 100.0);
-            let p2 = (Id::new(), 0.75, 75.0);
+            let p1 = (ids.allocate(), 0.25, 100.0);
+            let p2 = (ids.allocate(), 0.75, 75.0);
             redundancy.ratings.insert(p1.0, p1.1);
             redundancy.ratings.insert(p2.0, p2.1);
 
-            let subtask = SubTask::new(100.0, 100.0);
+            let subtask = SubTask::new(ids.allocate(), 100.0, 100.0);
             redundancy.verification_map.insert_key(*subtask.id());
 
             assert_eq!(
@@ -315,14 +365,15 @@ mod tests {
 
     #[test]
     fn verify_subtask_failed() {
-        let mut redundancy = Redundancy::new(Id::new());
+        let mut ids = IdAllocator::new();
+        let mut redundancy = Redundancy::new(ids.allocate());
 
-        let p1 = (Id::new(), 0.25, 100.0);
-        let p2 = (Id::new(), 0.75, 75.0);
+        let p1 = (ids.allocate(), 0.25, 100.0);
+        let p2 = (ids.allocate(), 0.75, 75.0);
         redundancy.ratings.insert(p1.0, p1.1);
         redundancy.ratings.insert(p2.0, p2.1);
 
-        let subtask = SubTask::new(100.0, 100.0);
+        let subtask = SubTask::new(ids.allocate(), 100.0, 100.0);
         redundancy.verification_map.insert_key(*subtask.id());
 
         assert_eq!(
@@ -1,8 +1,10 @@
 use std::collections::VecDeque;
 
+use serde_derive::{Deserialize, Serialize};
+
 use crate::task::Task;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TaskQueue {
     buffer: VecDeque<Task>,
     pub repeating: bool,
@@ -27,6 +29,20 @@ impl TaskQueue {
         })
     }
 
+    // drops the tasks none of whose subtasks can be computed in time anymore,
+    // which would otherwise be handed out over and over by a repeating queue
+    pub fn drop_expired(&mut self, now: f64) {
+        self.buffer.retain(|task| !task.has_expired(now));
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
     pub fn append<It: IntoIterator<Item = Task>>(&mut self, tasks: It) {
         for task in tasks {
             self.push(task)
@@ -47,13 +63,17 @@ impl Default for TaskQueue {
 mod tests {
     use super::*;
 
+    use crate::id::IdAllocator;
+    use crate::task::{Deadline, SubTask};
+
     #[test]
     fn pop_repeating() {
+        let mut ids = IdAllocator::new();
         let mut task_queue = TaskQueue::new();
 
         assert!(task_queue.repeating);
 
-        let task = Task::new();
+        let task = Task::new(ids.allocate());
         task_queue.push(task.clone());
 
         assert_eq!(task_queue.pop(), Some(task.clone()));
@@ -62,13 +82,33 @@ mod tests {
 
     #[test]
     fn pop_nonrepeating() {
+        let mut ids = IdAllocator::new();
         let mut task_queue = TaskQueue::new();
         task_queue.repeating = false;
 
-        let task = Task::new();
+        let task = Task::new(ids.allocate());
         task_queue.push(task.clone());
 
         assert_eq!(task_queue.pop(), Some(task));
         assert_eq!(task_queue.pop(), None);
+        assert!(task_queue.is_empty());
+    }
+
+    #[test]
+    fn drop_expired() {
+        let mut ids = IdAllocator::new();
+        let mut task_queue = TaskQueue::new();
+
+        let mut subtask = SubTask::new(ids.allocate(), 1.0, 1.0);
+        subtask.deadline = Some(Deadline::At(100.0));
+        let mut task = Task::new(ids.allocate());
+        task.push_pending(subtask);
+        task_queue.push(task.clone());
+
+        task_queue.drop_expired(50.0);
+        assert_eq!(task_queue.pop(), Some(task));
+
+        task_queue.drop_expired(100.0);
+        assert!(task_queue.is_empty());
     }
 }
@@ -0,0 +1,96 @@
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaChaRng;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde_derive::{Deserialize, Serialize};
+
+// ChaCha-based PRNG which, unlike ChaChaRng, can be checkpointed: its state is
+// fully described by the seed and the current position in the keystream
+#[derive(Clone, Debug)]
+pub struct SimRng {
+    seed: [u8; 32],
+    inner: ChaChaRng,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SimRngState {
+    seed: [u8; 32],
+    word_pos: u128,
+}
+
+impl SeedableRng for SimRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self {
+            seed,
+            inner: ChaChaRng::from_seed(seed),
+        }
+    }
+}
+
+impl RngCore for SimRng {
+    fn next_u32(&mut self) -> u32 {
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.inner.try_fill_bytes(dest)
+    }
+}
+
+impl Serialize for SimRng {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        SimRngState {
+            seed: self.seed,
+            word_pos: self.inner.get_word_pos(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SimRng {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let state = SimRngState::deserialize(deserializer)?;
+        let mut rng = Self::from_seed(state.seed);
+        rng.inner.set_word_pos(state.word_pos);
+
+        Ok(rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::Rng;
+
+    #[test]
+    fn serialize() {
+        let mut rng = SimRng::seed_from_u64(42);
+        let _: Vec<f64> = (0..13).map(|_| rng.gen()).collect();
+        let _: u32 = rng.gen();
+
+        let bytes = bincode::serialize(&rng).unwrap();
+        let mut restored: SimRng = bincode::deserialize(&bytes).unwrap();
+
+        let expected: Vec<u64> = (0..100).map(|_| rng.gen()).collect();
+        let actual: Vec<u64> = (0..100).map(|_| restored.gen()).collect();
+
+        assert_eq!(expected, actual);
+    }
+}
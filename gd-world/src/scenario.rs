@@ -0,0 +1,351 @@
+mod latency;
+mod source;
+mod spec;
+
+pub use self::latency::*;
+pub use self::source::*;
+pub use self::spec::*;
+
+use gd_engine::QueueKind;
+use rand::distributions::{Exp, LogNormal, Normal, Uniform};
+use rand::prelude::*;
+use serde::de::{self, Deserialize, Deserializer};
+use serde_derive::{Deserialize, Serialize};
+use statrs::function::erf;
+
+use crate::settlement::SettlementModel;
+use crate::visibility::Visibility;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Generator {
+    Fixed(f64),
+    Choice(Vec<f64>),
+    Uniform(f64, f64),
+    LogNormal(f64, f64),
+    Normal(f64, f64),
+    Exp(f64),
+}
+
+impl Generator {
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        match *self {
+            Generator::Fixed(value) => value,
+            Generator::Choice(ref values) => *values.choose(rng).unwrap_or(&0.0),
+            Generator::Uniform(min, max) => Uniform::new(min, max).sample(rng),
+            Generator::LogNormal(mean, std) => LogNormal::new(mean, std).sample(rng),
+            Generator::Normal(mean, std) => Normal::new(mean, std).sample(rng),
+            Generator::Exp(mean) => Exp::new(mean).sample(rng),
+        }
+    }
+
+    // value the samples stay below with probability `p`
+    pub fn quantile(&self, p: f64) -> f64 {
+        // quantile of the standard normal distribution
+        let z = std::f64::consts::SQRT_2 * erf::erf_inv(2.0 * p - 1.0);
+
+        match *self {
+            Generator::Fixed(value) => value,
+            Generator::Choice(ref values) if values.is_empty() => 0.0,
+            Generator::Choice(ref values) => {
+                let mut values = values.clone();
+                values.sort_by(|a, b| a.partial_cmp(b).expect("NaN value"));
+
+                let index = (p * values.len() as f64).ceil() as usize;
+                values[index.max(1).min(values.len()) - 1]
+            }
+            Generator::Uniform(min, max) => min + p * (max - min),
+            Generator::LogNormal(mean, std) => (mean + std * z).exp(),
+            Generator::Normal(mean, std) => mean + std * z,
+            // parametrised by the rate rather than the mean
+            Generator::Exp(rate) => -(1.0 - p).ln() / rate,
+        }
+    }
+
+    // whether the samples are positive, barring the odd zero drawn from
+    // a continuous distribution
+    pub fn is_positive(&self) -> bool {
+        match *self {
+            Generator::Fixed(value) => value > 0.0,
+            Generator::Choice(ref values) => {
+                !values.is_empty() && values.iter().all(|&value| value > 0.0)
+            }
+            Generator::Uniform(min, max) => min >= 0.0 && max > min,
+            Generator::LogNormal(..) | Generator::Exp(_) => true,
+            Generator::Normal(..) => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderBehaviour {
+    Regular,
+    UndercutBudget(f64),
+    LinearUsageInflation(f64),
+}
+
+impl Default for ProviderBehaviour {
+    fn default() -> Self {
+        ProviderBehaviour::Regular
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestorBehaviour {
+    #[default]
+    Honest,
+    // keeps the results, yet never pays for them
+    NeverPay,
+    // pays only this fraction of what is due
+    PayFraction(f64),
+    // pays in full, but this many seconds late
+    PayLate(f64),
+    // keeps the results, yet withdraws each subtask once computed, claiming
+    // it cancelled, and pays nothing
+    CancelAfterCompute,
+}
+
+// simulation clock; ticks count whole microseconds, and unlike seconds kept
+// as floating-point numbers, do not drift over long runs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Clock {
+    #[default]
+    Seconds,
+    Ticks,
+}
+
+// conditions, besides the duration, on which each run ends early
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StopParams {
+    // once the requestors with non-repeating task queues have computed them
+    #[serde(default)]
+    pub workload_done: bool,
+    pub max_events: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SimulationParams {
+    pub duration: f64,
+    pub seed: Option<u64>,
+    pub requestors: Option<Vec<RequestorSpec>>,
+    pub requestor_sources: Option<Vec<RequestorSource>>,
+    pub providers: Option<Vec<ProviderSpec>>,
+    pub provider_sources: Option<Vec<ProviderSource>>,
+    pub event_queue: Option<QueueKind>,
+    pub clock: Option<Clock>,
+    pub latency: Option<LatencyModel>,
+    pub visibility: Option<Visibility>,
+    pub settlement: Option<SettlementModel>,
+    pub stop: Option<StopParams>,
+}
+
+// a requestor allowed no tasks at all would never advertise any
+fn deserialize_max_concurrent_tasks<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<usize>::deserialize(deserializer)? {
+        Some(0) => Err(de::Error::custom("at least one task has to be allowed")),
+        max_concurrent_tasks => Ok(max_concurrent_tasks),
+    }
+}
+
+fn deserialize_requestor_behaviour<'de, D>(deserializer: D) -> Result<RequestorBehaviour, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match RequestorBehaviour::deserialize(deserializer)? {
+        RequestorBehaviour::PayFraction(fraction) if !(0.0..=1.0).contains(&fraction) => {
+            Err(de::Error::custom("fraction paid must lie within [0, 1]"))
+        }
+        RequestorBehaviour::PayLate(delay) if delay < 0.0 => {
+            Err(de::Error::custom("payment delay cannot be negative"))
+        }
+        behaviour => Ok(behaviour),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_test::{assert_de_tokens, Token};
+    use statrs::assert_almost_eq;
+
+    #[test]
+    fn quantile() {
+        assert_eq!(Generator::Fixed(2.0).quantile(0.999), 2.0);
+        assert_eq!(Generator::Choice(vec![3.0, 1.0, 2.0]).quantile(0.5), 2.0);
+        assert_eq!(Generator::Choice(vec![3.0, 1.0, 2.0]).quantile(0.999), 3.0);
+        assert_eq!(Generator::Choice(vec![]).quantile(0.999), 0.0);
+        assert_almost_eq!(Generator::Uniform(1.0, 3.0).quantile(0.75), 2.5, 1e-9);
+        assert_almost_eq!(Generator::Normal(1.0, 2.0).quantile(0.5), 1.0, 1e-9);
+        assert_almost_eq!(Generator::Normal(0.0, 1.0).quantile(0.999), 3.0902, 1e-4);
+        assert_almost_eq!(
+            Generator::LogNormal(0.0, 1.0).quantile(0.999),
+            3.0902f64.exp(),
+            1e-2
+        );
+        assert_almost_eq!(Generator::Exp(0.5).quantile(0.999), 13.8155, 1e-4);
+    }
+
+    #[test]
+    fn deserialize_provider_behaviour() {
+        assert_de_tokens(
+            &ProviderBehaviour::Regular,
+            &[
+                Token::Enum {
+                    name: "ProviderBehaviour",
+                },
+                Token::Str("regular"),
+                Token::Unit,
+            ],
+        );
+
+        assert_de_tokens(
+            &ProviderBehaviour::UndercutBudget(0.01),
+            &[
+                Token::Enum {
+                    name: "ProviderBehaviour",
+                },
+                Token::Str("undercut_budget"),
+                Token::F64(0.01),
+            ],
+        );
+
+        assert_de_tokens(
+            &ProviderBehaviour::LinearUsageInflation(1.5),
+            &[
+                Token::Enum {
+                    name: "ProviderBehaviour",
+                },
+                Token::Str("linear_usage_inflation"),
+                Token::F64(1.5),
+            ],
+        );
+    }
+
+    #[test]
+    fn deserialize_requestor_behaviour() {
+        assert_de_tokens(
+            &RequestorBehaviour::NeverPay,
+            &[
+                Token::Enum {
+                    name: "RequestorBehaviour",
+                },
+                Token::Str("never_pay"),
+                Token::Unit,
+            ],
+        );
+
+        assert_de_tokens(
+            &RequestorBehaviour::PayFraction(0.5),
+            &[
+                Token::Enum {
+                    name: "RequestorBehaviour",
+                },
+                Token::Str("pay_fraction"),
+                Token::F64(0.5),
+            ],
+        );
+
+        assert_de_tokens(
+            &RequestorBehaviour::PayLate(3600.0),
+            &[
+                Token::Enum {
+                    name: "RequestorBehaviour",
+                },
+                Token::Str("pay_late"),
+                Token::F64(3600.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn deserialize_fixed() {
+        assert_de_tokens(
+            &Generator::Fixed(1.0),
+            &[
+                Token::Enum { name: "Generator" },
+                Token::Str("fixed"),
+                Token::F64(1.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn deserialize_choice() {
+        assert_de_tokens(
+            &Generator::Choice(vec![0.5, 1.0, 2.0]),
+            &[
+                Token::Enum { name: "Generator" },
+                Token::Str("choice"),
+                Token::Seq { len: Some(3) },
+                Token::F64(0.5),
+                Token::F64(1.0),
+                Token::F64(2.0),
+                Token::SeqEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn deserialize_uniform() {
+        assert_de_tokens(
+            &Generator::Uniform(1.0, 2.0),
+            &[
+                Token::Enum { name: "Generator" },
+                Token::Str("uniform"),
+                Token::Seq { len: Some(2) },
+                Token::F64(1.0),
+                Token::F64(2.0),
+                Token::SeqEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn deserialize_lognormal() {
+        assert_de_tokens(
+            &Generator::LogNormal(0.0, 1.0),
+            &[
+                Token::Enum { name: "Generator" },
+                Token::Str("lognormal"),
+                Token::Seq { len: Some(2) },
+                Token::F64(0.0),
+                Token::F64(1.0),
+                Token::SeqEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn deserialize_normal() {
+        assert_de_tokens(
+            &Generator::Normal(0.0, 0.5),
+            &[
+                Token::Enum { name: "Generator" },
+                Token::Str("normal"),
+                Token::Seq { len: Some(2) },
+                Token::F64(0.0),
+                Token::F64(0.5),
+                Token::SeqEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn deserialize_exp() {
+        assert_de_tokens(
+            &Generator::Exp(1.0),
+            &[
+                Token::Enum { name: "Generator" },
+                Token::Str("exp"),
+                Token::F64(1.0),
+            ],
+        );
+    }
+}
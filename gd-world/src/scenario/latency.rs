@@ -0,0 +1,96 @@
+use serde_derive::{Deserialize, Serialize};
+
+use super::Generator;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message {
+    // provider's offer in response to an advertisement
+    Offer,
+    // subtask sent by the requestor to the provider
+    SubTask,
+    // result, or lack thereof, reported by the provider
+    Result,
+    Payment,
+}
+
+// delays of the messages exchanged between requestors and providers; messages
+// of the types left unspecified are delivered instantly
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LatencyModel {
+    offer: Option<Generator>,
+    subtask: Option<Generator>,
+    result: Option<Generator>,
+    payment: Option<Generator>,
+}
+
+impl LatencyModel {
+    // fraction of the messages delivered within the bound on the round trip
+    const QUANTILE: f64 = 0.999;
+
+    pub fn sample<Rng>(&self, message: Message, rng: &mut Rng) -> Option<f64>
+    where
+        Rng: rand::Rng,
+    {
+        let generator = match message {
+            Message::Offer => &self.offer,
+            Message::SubTask => &self.subtask,
+            Message::Result => &self.result,
+            Message::Payment => &self.payment,
+        };
+
+        generator
+            .as_ref()
+            .map(|generator| generator.sample(rng).max(0.0))
+    }
+
+    // time a subtask and its result spend in transit, barring the rare
+    // message taking longer than the quantile
+    pub fn round_trip(&self) -> f64 {
+        [&self.subtask, &self.result]
+            .iter()
+            .filter_map(|generator| generator.as_ref())
+            .fold(0.0, |sum, generator| {
+                sum + generator.quantile(Self::QUANTILE).max(0.0)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+    use statrs::assert_almost_eq;
+
+    use crate::rng::SimRng;
+
+    #[test]
+    fn sample() {
+        let model: LatencyModel = serde_json::from_str(
+            r#"{
+                "offer": { "fixed": 0.5 },
+                "result": { "normal": [-10.0, 0.1] }
+            }"#,
+        )
+        .unwrap();
+        let mut rng = SimRng::seed_from_u64(0);
+
+        assert_eq!(model.sample(Message::Offer, &mut rng), Some(0.5));
+        assert_eq!(model.sample(Message::SubTask, &mut rng), None);
+        assert_eq!(model.sample(Message::Result, &mut rng), Some(0.0));
+        assert_eq!(model.sample(Message::Payment, &mut rng), None);
+
+        assert_eq!(model.round_trip(), 0.0);
+        assert_eq!(LatencyModel::default().round_trip(), 0.0);
+
+        let model: LatencyModel = serde_json::from_str(
+            r#"{
+                "offer": { "fixed": 0.5 },
+                "subtask": { "uniform": [1.0, 3.0] },
+                "result": { "exp": 0.25 }
+            }"#,
+        )
+        .unwrap();
+        assert_almost_eq!(model.round_trip(), 2.998 + 27.631, 1e-3);
+    }
+}
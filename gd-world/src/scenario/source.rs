@@ -0,0 +1,331 @@
+use serde::de::{self, Deserialize, Deserializer};
+use serde_derive::{Deserialize, Serialize};
+
+use super::{
+    deserialize_max_concurrent_tasks, deserialize_requestor_behaviour, Generator,
+    ProviderBehaviour, RequestorBehaviour,
+};
+use crate::id::IdAllocator;
+use crate::provider::{
+    LinearUsageInflationProvider, Provider, RegularProvider, UndercutBudgetProvider,
+};
+use crate::requestor::{DefenceMechanismType, Requestor};
+use crate::task::{Deadline, SubTask, Task};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestorSource {
+    requestor_count: usize,
+    max_price: Generator,
+    budget_factor: Generator,
+    subtask_count: Generator,
+    nominal_usage: Generator,
+    deadline: Option<Deadline>,
+    #[serde(default, deserialize_with = "deserialize_max_concurrent_tasks")]
+    max_concurrent_tasks: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_requestor_behaviour")]
+    behaviour: RequestorBehaviour,
+    // time between consecutive arrivals of new requestors during the simulation
+    #[serde(default, deserialize_with = "deserialize_arrival")]
+    arrival: Option<Generator>,
+    // time each requestor stays in the market for
+    lifetime: Option<Generator>,
+}
+
+impl RequestorSource {
+    pub fn has_churn(&self) -> bool {
+        self.arrival.is_some() || self.lifetime.is_some()
+    }
+
+    pub fn sample_requestor<Rng>(
+        &self,
+        ids: &mut IdAllocator,
+        rng: &mut Rng,
+        defence_mechanism_type: DefenceMechanismType,
+    ) -> Requestor
+    where
+        Rng: rand::Rng,
+    {
+        let mut requestor = Requestor::new(
+            ids.allocate(),
+            self.max_price.sample(rng),
+            self.budget_factor.sample(rng),
+            defence_mechanism_type,
+        );
+
+        let count = self.subtask_count.sample(rng).round() as usize;
+        let mut task = Task::new(ids.allocate());
+
+        for _ in 0..count {
+            let nominal_usage = self.nominal_usage.sample(rng);
+            let budget = requestor.max_price() * requestor.budget_factor() * nominal_usage;
+            let mut subtask = SubTask::new(ids.allocate(), nominal_usage, budget);
+            subtask.deadline = self.deadline;
+
+            task.push_pending(subtask);
+        }
+
+        requestor.task_queue_mut().push(task);
+        requestor.set_behaviour(self.behaviour);
+
+        if let Some(max_concurrent_tasks) = self.max_concurrent_tasks {
+            requestor.set_max_concurrent_tasks(max_concurrent_tasks);
+        }
+
+        requestor
+    }
+
+    pub fn sample_arrival<Rng>(&self, rng: &mut Rng) -> Option<f64>
+    where
+        Rng: rand::Rng,
+    {
+        self.arrival.as_ref().map(|arrival| arrival.sample(rng))
+    }
+
+    pub fn sample_lifetime<Rng>(&self, rng: &mut Rng) -> Option<f64>
+    where
+        Rng: rand::Rng,
+    {
+        self.lifetime
+            .as_ref()
+            .map(|lifetime| lifetime.sample(rng).max(0.0))
+    }
+
+    pub fn iter<'a, Rng>(
+        &'a self,
+        ids: &'a mut IdAllocator,
+        rng: &'a mut Rng,
+        defence_mechanism_type: DefenceMechanismType,
+    ) -> RequestorSourceIter<'a, Rng>
+    where
+        Rng: rand::Rng + 'static,
+    {
+        RequestorSourceIter {
+            count: 0,
+            source: self,
+            ids,
+            rng,
+            defence_mechanism_type,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RequestorSourceIter<'a, Rng>
+where
+    Rng: rand::Rng + 'static,
+{
+    count: usize,
+    source: &'a RequestorSource,
+    ids: &'a mut IdAllocator,
+    rng: &'a mut Rng,
+    defence_mechanism_type: DefenceMechanismType,
+}
+
+impl<'a, Rng> Iterator for RequestorSourceIter<'a, Rng>
+where
+    Rng: rand::Rng + 'static,
+{
+    type Item = Requestor;
+
+    fn next(&mut self) -> Option<Requestor> {
+        if self.count >= self.source.requestor_count {
+            return None;
+        }
+
+        self.count += 1;
+
+        Some(
+            self.source
+                .sample_requestor(self.ids, self.rng, self.defence_mechanism_type),
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProviderSource {
+    provider_count: usize,
+    min_price: Generator,
+    usage_factor: Generator,
+    #[serde(default)]
+    behaviour: ProviderBehaviour,
+    capacity: Option<Generator>,
+    failure_rate: Option<Generator>,
+    // time between consecutive arrivals of new providers during the simulation
+    #[serde(default, deserialize_with = "deserialize_arrival")]
+    arrival: Option<Generator>,
+    // time each provider stays in the market for
+    lifetime: Option<Generator>,
+}
+
+impl ProviderSource {
+    pub fn has_churn(&self) -> bool {
+        self.arrival.is_some() || self.lifetime.is_some()
+    }
+
+    pub fn sample_provider<Rng>(
+        &self,
+        ids: &mut IdAllocator,
+        rng: &mut Rng,
+    ) -> Box<dyn Provider<Rng = Rng>>
+    where
+        Rng: rand::Rng + 'static,
+    {
+        let id = ids.allocate();
+        let min_price = self.min_price.sample(rng);
+        let usage_factor = self.usage_factor.sample(rng);
+
+        let mut provider: Box<dyn Provider<Rng = Rng>> =
+            match self.behaviour {
+                ProviderBehaviour::UndercutBudget(epsilon) => Box::new(
+                    UndercutBudgetProvider::new(id, min_price, usage_factor, epsilon),
+                ),
+                ProviderBehaviour::LinearUsageInflation(factor) => Box::new(
+                    LinearUsageInflationProvider::new(id, min_price, usage_factor, factor),
+                ),
+                _ => Box::new(RegularProvider::new(id, min_price, usage_factor)),
+            };
+
+        if let Some(capacity) = &self.capacity {
+            provider.set_capacity((capacity.sample(rng).round() as usize).max(1));
+        }
+
+        if let Some(failure_rate) = &self.failure_rate {
+            provider.set_failure_rate(failure_rate.sample(rng).max(0.0));
+        }
+
+        provider
+    }
+
+    pub fn sample_arrival<Rng>(&self, rng: &mut Rng) -> Option<f64>
+    where
+        Rng: rand::Rng,
+    {
+        self.arrival.as_ref().map(|arrival| arrival.sample(rng))
+    }
+
+    pub fn sample_lifetime<Rng>(&self, rng: &mut Rng) -> Option<f64>
+    where
+        Rng: rand::Rng,
+    {
+        self.lifetime
+            .as_ref()
+            .map(|lifetime| lifetime.sample(rng).max(0.0))
+    }
+
+    pub fn iter<'a, Rng>(
+        &'a self,
+        ids: &'a mut IdAllocator,
+        rng: &'a mut Rng,
+    ) -> ProviderSourceIter<'a, Rng>
+    where
+        Rng: rand::Rng + 'static,
+    {
+        ProviderSourceIter {
+            count: 0,
+            source: self,
+            ids,
+            rng,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ProviderSourceIter<'a, Rng>
+where
+    Rng: rand::Rng + 'static,
+{
+    count: usize,
+    source: &'a ProviderSource,
+    ids: &'a mut IdAllocator,
+    rng: &'a mut Rng,
+}
+
+impl<'a, Rng> Iterator for ProviderSourceIter<'a, Rng>
+where
+    Rng: rand::Rng + 'static,
+{
+    type Item = Box<dyn Provider<Rng = Rng>>;
+
+    fn next(&mut self) -> Option<Box<dyn Provider<Rng = Rng>>> {
+        if self.count >= self.source.provider_count {
+            return None;
+        }
+
+        self.count += 1;
+
+        Some(self.source.sample_provider(self.ids, self.rng))
+    }
+}
+
+// arrivals spaced by zero (or negative) time would never let the clock move on
+fn deserialize_arrival<'de, D>(deserializer: D) -> Result<Option<Generator>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let arrival = Option::<Generator>::deserialize(deserializer)?;
+
+    match arrival {
+        Some(generator) if !generator.is_positive() => Err(de::Error::custom(
+            "time between arrivals has to be positive",
+        )),
+        arrival => Ok(arrival),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_arrival() {
+        let source = r#"{
+            "provider_count": 1,
+            "min_price": { "fixed": 0.00001 },
+            "usage_factor": { "fixed": 1.0 },
+            "arrival": { "exp": 0.001 }
+        }"#;
+        let source: ProviderSource = serde_json::from_str(source).unwrap();
+        assert!(source.has_churn());
+
+        for arrival in &[
+            r#"{ "fixed": 0 }"#,
+            r#"{ "choice": [60, 0] }"#,
+            r#"{ "uniform": [-1, 1] }"#,
+            r#"{ "normal": [3600, 600] }"#,
+        ] {
+            let source = format!(
+                r#"{{
+                    "requestor_count": 1,
+                    "max_price": {{ "fixed": 0.0001 }},
+                    "budget_factor": {{ "fixed": 1.0 }},
+                    "subtask_count": {{ "fixed": 1 }},
+                    "nominal_usage": {{ "fixed": 3600 }},
+                    "arrival": {}
+                }}"#,
+                arrival
+            );
+            assert!(serde_json::from_str::<RequestorSource>(&source).is_err());
+        }
+    }
+
+    #[test]
+    fn deserialize_max_concurrent_tasks() {
+        let source = |max_concurrent_tasks| {
+            format!(
+                r#"{{
+                    "requestor_count": 1,
+                    "max_price": {{ "fixed": 0.0001 }},
+                    "budget_factor": {{ "fixed": 1.0 }},
+                    "subtask_count": {{ "fixed": 1 }},
+                    "nominal_usage": {{ "fixed": 3600 }},
+                    "max_concurrent_tasks": {}
+                }}"#,
+                max_concurrent_tasks
+            )
+        };
+
+        let requestor: RequestorSource = serde_json::from_str(&source(3)).unwrap();
+        assert_eq!(requestor.max_concurrent_tasks, Some(3));
+        assert!(serde_json::from_str::<RequestorSource>(&source(0)).is_err());
+    }
+}
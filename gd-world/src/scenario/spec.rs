@@ -0,0 +1,235 @@
+use serde::de::{self, Deserialize, Deserializer};
+use serde_derive::Deserialize;
+
+use super::{
+    deserialize_max_concurrent_tasks, deserialize_requestor_behaviour, Generator,
+    ProviderBehaviour, RequestorBehaviour,
+};
+use crate::id::{Id, IdAllocator};
+use crate::provider::{
+    LinearUsageInflationProvider, Provider, RegularProvider, UndercutBudgetProvider,
+};
+use crate::requestor::{DefenceMechanismType, Requestor};
+use crate::task::{Deadline, SubTask, Task};
+
+#[derive(Debug, Deserialize)]
+pub struct RequestorSpec {
+    // allocated if left out
+    id: Option<Id>,
+    max_price: f64,
+    budget_factor: f64,
+    tasks: Vec<TaskSpec>,
+    #[serde(default)]
+    repeating: bool,
+    #[serde(default, deserialize_with = "deserialize_max_concurrent_tasks")]
+    max_concurrent_tasks: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_requestor_behaviour")]
+    behaviour: RequestorBehaviour,
+}
+
+impl RequestorSpec {
+    pub fn id(&self) -> Option<Id> {
+        self.id
+    }
+
+    pub fn as_requestor<Rng>(
+        &self,
+        ids: &mut IdAllocator,
+        rng: &mut Rng,
+        defence_mechanism_type: DefenceMechanismType,
+    ) -> Requestor
+    where
+        Rng: rand::Rng,
+    {
+        let id = self.id.unwrap_or_else(|| ids.allocate());
+        let mut requestor = Requestor::new(
+            id,
+            self.max_price,
+            self.budget_factor,
+            defence_mechanism_type,
+        );
+        requestor.task_queue_mut().append(
+            self.tasks
+                .iter()
+                .map(|t| t.as_task(ids, rng, self.max_price, self.budget_factor)),
+        );
+        requestor.task_queue_mut().repeating = self.repeating;
+        requestor.set_behaviour(self.behaviour);
+
+        if let Some(max_concurrent_tasks) = self.max_concurrent_tasks {
+            requestor.set_max_concurrent_tasks(max_concurrent_tasks);
+        }
+
+        requestor
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaskSpec {
+    subtask_count: usize,
+    nominal_usage: Generator,
+    deadline: Option<Deadline>,
+}
+
+impl TaskSpec {
+    pub fn as_task<Rng>(
+        &self,
+        ids: &mut IdAllocator,
+        rng: &mut Rng,
+        max_price: f64,
+        budget_factor: f64,
+    ) -> Task
+    where
+        Rng: rand::Rng,
+    {
+        let mut task = Task::new(ids.allocate());
+
+        for _ in 0..self.subtask_count {
+            let nominal_usage = self.nominal_usage.sample(rng);
+            let budget = max_price * budget_factor * nominal_usage;
+            let mut subtask = SubTask::new(ids.allocate(), nominal_usage, budget);
+            subtask.deadline = self.deadline;
+
+            task.push_pending(subtask);
+        }
+
+        task
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProviderSpec {
+    // allocated if left out
+    id: Option<Id>,
+    min_price: f64,
+    usage_factor: f64,
+    #[serde(default)]
+    behaviour: ProviderBehaviour,
+    #[serde(default, deserialize_with = "deserialize_capacity")]
+    capacity: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_failure_rate")]
+    failure_rate: Option<f64>,
+}
+
+impl ProviderSpec {
+    pub fn id(&self) -> Option<Id> {
+        self.id
+    }
+
+    pub fn as_provider<Rng>(&self, ids: &mut IdAllocator) -> Box<dyn Provider<Rng = Rng>>
+    where
+        Rng: rand::Rng + 'static,
+    {
+        let id = self.id.unwrap_or_else(|| ids.allocate());
+        let mut provider: Box<dyn Provider<Rng = Rng>> = match self.behaviour {
+            ProviderBehaviour::UndercutBudget(epsilon) => Box::new(UndercutBudgetProvider::new(
+                id,
+                self.min_price,
+                self.usage_factor,
+                epsilon,
+            )),
+            ProviderBehaviour::LinearUsageInflation(factor) => Box::new(
+                LinearUsageInflationProvider::new(id, self.min_price, self.usage_factor, factor),
+            ),
+            _ => Box::new(RegularProvider::new(id, self.min_price, self.usage_factor)),
+        };
+
+        if let Some(capacity) = self.capacity {
+            provider.set_capacity(capacity);
+        }
+
+        if let Some(failure_rate) = self.failure_rate {
+            provider.set_failure_rate(failure_rate);
+        }
+
+        provider
+    }
+}
+
+// a provider without a single slot could never compute anything
+fn deserialize_capacity<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<usize>::deserialize(deserializer)? {
+        Some(0) => Err(de::Error::custom("provider needs at least one slot")),
+        capacity => Ok(capacity),
+    }
+}
+
+fn deserialize_failure_rate<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<f64>::deserialize(deserializer)? {
+        Some(failure_rate) if failure_rate < 0.0 => {
+            Err(de::Error::custom("failure rate cannot be negative"))
+        }
+        failure_rate => Ok(failure_rate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_requestor() {
+        let spec = |field: &str| {
+            format!(
+                r#"{{
+                    "max_price": 0.0001,
+                    "budget_factor": 1.0,
+                    "tasks": [],
+                    {}
+                }}"#,
+                field
+            )
+        };
+
+        let requestor: RequestorSpec =
+            serde_json::from_str(&spec(r#""max_concurrent_tasks": 2"#)).unwrap();
+        assert_eq!(requestor.max_concurrent_tasks, Some(2));
+
+        assert!(
+            serde_json::from_str::<RequestorSpec>(&spec(r#""max_concurrent_tasks": 0"#)).is_err()
+        );
+
+        let requestor: RequestorSpec =
+            serde_json::from_str(&spec(r#""behaviour": { "pay_fraction": 0.5 }"#)).unwrap();
+        assert_eq!(requestor.behaviour, RequestorBehaviour::PayFraction(0.5));
+
+        for behaviour in &[
+            r#""behaviour": { "pay_fraction": 1.5 }"#,
+            r#""behaviour": { "pay_fraction": -0.5 }"#,
+            r#""behaviour": { "pay_late": -1 }"#,
+        ] {
+            assert!(serde_json::from_str::<RequestorSpec>(&spec(behaviour)).is_err());
+        }
+    }
+
+    #[test]
+    fn deserialize_provider() {
+        let spec = |field: &str| {
+            format!(
+                r#"{{
+                    "min_price": 0.00001,
+                    "usage_factor": 1.0,
+                    {}
+                }}"#,
+                field
+            )
+        };
+
+        let provider: ProviderSpec = serde_json::from_str(&spec(r#""capacity": 4"#)).unwrap();
+        assert_eq!(provider.capacity, Some(4));
+
+        assert!(serde_json::from_str::<ProviderSpec>(&spec(r#""capacity": 0"#)).is_err());
+
+        let provider: ProviderSpec =
+            serde_json::from_str(&spec(r#""failure_rate": 0.001"#)).unwrap();
+        assert_eq!(provider.failure_rate, Some(0.001));
+
+        assert!(serde_json::from_str::<ProviderSpec>(&spec(r#""failure_rate": -0.001"#)).is_err());
+    }
+}
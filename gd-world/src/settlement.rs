@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::id::Id;
+use crate::task::SubTask;
+
+// how payments make their way from requestors to providers; each requestor
+// gathers its payments into batches, and settles each batch in a single
+// transaction
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SettlementModel {
+    // time a transaction takes to go through
+    #[serde(default, deserialize_with = "deserialize_non_negative")]
+    pub delay: f64,
+    // time a batch stays open for since its first payment; each payment is
+    // settled on its own if 0
+    #[serde(default, deserialize_with = "deserialize_non_negative")]
+    pub batch_window: f64,
+    // fee per transaction, borne by the requestor
+    #[serde(default, deserialize_with = "deserialize_non_negative")]
+    pub fixed_fee: f64,
+    // fee per transaction, as a fraction of the amount transferred
+    #[serde(default, deserialize_with = "deserialize_non_negative")]
+    pub proportional_fee: f64,
+}
+
+impl SettlementModel {
+    pub fn fee(&self, amount: f64) -> f64 {
+        self.fixed_fee + self.proportional_fee * amount
+    }
+}
+
+// neither time nor money can be negative
+fn deserialize_non_negative<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = f64::deserialize(deserializer)?;
+
+    if value < 0.0 {
+        return Err(de::Error::custom(
+            "settlement parameters cannot be negative",
+        ));
+    }
+
+    Ok(value)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Payment {
+    pub subtask: SubTask,
+    pub provider_id: Id,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Settlement {
+    model: SettlementModel,
+    // payments gathered by each requestor since its last transaction
+    batches: BTreeMap<Id, Vec<Payment>>,
+}
+
+impl Settlement {
+    pub fn new(model: SettlementModel) -> Self {
+        assert!(model.delay >= 0.0, "settlement delay cannot be negative");
+        assert!(model.batch_window >= 0.0, "batch window cannot be negative");
+        assert!(
+            model.fixed_fee >= 0.0 && model.proportional_fee >= 0.0,
+            "fees cannot be negative"
+        );
+
+        Self {
+            model,
+            batches: BTreeMap::new(),
+        }
+    }
+
+    pub fn model(&self) -> &SettlementModel {
+        &self.model
+    }
+
+    // adds the payment to the requestor's batch; returns true if it opened
+    // a new one
+    pub fn submit(&mut self, requestor_id: Id, payment: Payment) -> bool {
+        let batch = self.batches.entry(requestor_id).or_default();
+        batch.push(payment);
+
+        batch.len() == 1
+    }
+
+    // closes the requestor's batch; returns the payments in it along with
+    // the fee of the transaction settling them
+    pub fn close(&mut self, requestor_id: Id) -> (Vec<Payment>, f64) {
+        let payments = self.batches.remove(&requestor_id).unwrap_or_default();
+        let amount = payments.iter().map(|payment| payment.amount).sum();
+
+        (payments, self.model.fee(amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use statrs::assert_almost_eq;
+
+    use crate::id::IdAllocator;
+
+    #[test]
+    fn batches() {
+        let mut ids = IdAllocator::new();
+        let mut settlement = Settlement::new(SettlementModel {
+            fixed_fee: 0.1,
+            proportional_fee: 0.01,
+            ..SettlementModel::default()
+        });
+        let (r1, r2) = (ids.allocate(), ids.allocate());
+        let payment = |ids: &mut IdAllocator, amount| Payment {
+            subtask: SubTask::new(ids.allocate(), 1.0, 1.0),
+            provider_id: ids.allocate(),
+            amount,
+        };
+
+        assert!(settlement.submit(r1, payment(&mut ids, 10.0)));
+        assert!(!settlement.submit(r1, payment(&mut ids, 20.0)));
+        assert!(settlement.submit(r2, payment(&mut ids, 5.0)));
+
+        let (payments, fee) = settlement.close(r1);
+        assert_eq!(payments.len(), 2);
+        assert_almost_eq!(fee, 0.1 + 0.01 * 30.0, 1e-9);
+
+        // the next payment opens a fresh batch
+        assert!(settlement.submit(r1, payment(&mut ids, 1.0)));
+
+        let (payments, fee) = settlement.close(r2);
+        assert_eq!(payments.len(), 1);
+        assert_almost_eq!(fee, 0.15, 1e-9);
+    }
+
+    #[test]
+    fn deserialize_model() {
+        let model: SettlementModel =
+            serde_json::from_str(r#"{ "delay": 600, "fixed_fee": 0.01 }"#).unwrap();
+        assert_eq!(
+            model,
+            SettlementModel {
+                delay: 600.0,
+                fixed_fee: 0.01,
+                ..SettlementModel::default()
+            }
+        );
+
+        for model in &[
+            r#"{ "delay": -1 }"#,
+            r#"{ "batch_window": -60 }"#,
+            r#"{ "fixed_fee": -0.01 }"#,
+            r#"{ "proportional_fee": -0.01 }"#,
+        ] {
+            assert!(serde_json::from_str::<SettlementModel>(model).is_err());
+        }
+    }
+}
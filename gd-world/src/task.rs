@@ -1,28 +1,49 @@
 use std::collections::VecDeque;
 use std::fmt;
 
-use crate::id::Id;
+use serde_derive::{Deserialize, Serialize};
 
-pub use subtask::SubTask;
+use crate::id::{Id, IdAllocator};
 
-#[derive(Clone, Debug, Default)]
+pub use subtask::{Deadline, SubTask};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Task {
     id: Id,
     size: usize,
     pending: VecDeque<SubTask>,
     done: VecDeque<SubTask>,
+    // subtasks dropped once their absolute deadline passed
+    expired: VecDeque<SubTask>,
 }
 
 impl Task {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(id: Id) -> Self {
+        Self {
+            id,
+            size: 0,
+            pending: VecDeque::new(),
+            done: VecDeque::new(),
+            expired: VecDeque::new(),
+        }
     }
 
-    pub fn push_pending(&mut self, subtask: SubTask) {
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+
+    pub fn push_pending(&mut self, mut subtask: SubTask) {
+        subtask.task_id = Some(self.id);
+
         self.size += 1;
         self.pending.push_back(subtask);
     }
 
+    // puts a subtask handed out before back to pending
+    pub fn requeue(&mut self, subtask: SubTask) {
+        self.pending.push_back(subtask);
+    }
+
     pub fn pop_pending(&mut self) -> Option<SubTask> {
         self.pending.pop_front()
     }
@@ -38,6 +59,56 @@ impl Task {
     pub fn is_done(&self) -> bool {
         self.done.len() == self.size
     }
+
+    // drops the pending subtasks whose absolute deadline has passed, and
+    // returns how many of them there were
+    pub fn expire(&mut self, now: f64) -> usize {
+        let (expired, pending): (VecDeque<_>, VecDeque<_>) = self
+            .pending
+            .drain(..)
+            .partition(|subtask| subtask.has_expired(now));
+
+        self.pending = pending;
+        let count = expired.len();
+        self.expired.extend(expired);
+
+        count
+    }
+
+    // whether every subtask has either been computed or expired
+    pub fn is_finished(&self) -> bool {
+        self.done.len() + self.expired.len() == self.size
+    }
+
+    // whether none of the subtasks can be computed in time anymore
+    pub fn has_expired(&self, now: f64) -> bool {
+        self.size > 0
+            && self
+                .pending
+                .iter()
+                .chain(self.done.iter())
+                .chain(self.expired.iter())
+                .all(|subtask| subtask.has_expired(now))
+    }
+
+    // a fresh copy of the task, with new ids and all subtasks pending
+    pub fn respawn(&self, ids: &mut IdAllocator) -> Self {
+        let mut task = Self::new(ids.allocate());
+
+        for subtask in self
+            .pending
+            .iter()
+            .chain(self.done.iter())
+            .chain(self.expired.iter())
+        {
+            let mut copy = SubTask::new(ids.allocate(), subtask.nominal_usage, subtask.budget);
+            copy.deadline = subtask.deadline;
+
+            task.push_pending(copy);
+        }
+
+        task
+    }
 }
 
 impl std::fmt::Display for Task {
@@ -69,25 +140,66 @@ pub mod subtask {
         Done,
     }
 
-    #[derive(Clone, Copy, Debug)]
+    // time by which the subtask has to be reported back on
+    #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Deadline {
+        // absolute simulation time
+        At(f64),
+        // seconds since the subtask was handed out to the provider
+        After(f64),
+    }
+
+    impl Deadline {
+        // time left until the deadline for a subtask handed out at `now`
+        pub fn time_left(&self, now: f64) -> f64 {
+            match *self {
+                Deadline::At(time) => (time - now).max(0.0),
+                Deadline::After(duration) => duration,
+            }
+        }
+
+        // whether the subtask can no longer be handed out at `now`
+        pub fn has_passed(&self, now: f64) -> bool {
+            match *self {
+                Deadline::At(time) => time <= now,
+                Deadline::After(_) => false,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
     pub struct SubTask {
         id: Id,
+        pub(super) task_id: Option<Id>,
         pub nominal_usage: f64,
         pub budget: f64,
+        pub deadline: Option<Deadline>,
     }
 
     impl SubTask {
-        pub fn new(nominal_usage: f64, budget: f64) -> Self {
+        pub fn new(id: Id, nominal_usage: f64, budget: f64) -> Self {
             Self {
-                id: Id::new(),
+                id,
+                task_id: None,
                 nominal_usage,
                 budget,
+                deadline: None,
             }
         }
 
         pub fn id(&self) -> &Id {
             &self.id
         }
+
+        // the task this subtask belongs to, once pushed into one
+        pub fn task_id(&self) -> Option<Id> {
+            self.task_id
+        }
+
+        pub fn has_expired(&self, now: f64) -> bool {
+            matches!(self.deadline, Some(deadline) if deadline.has_passed(now))
+        }
     }
 
     impl std::fmt::Display for SubTask {
@@ -113,12 +225,13 @@ mod tests {
 
     #[test]
     fn task_states() {
-        let mut task = Task::new();
+        let mut ids = IdAllocator::new();
+        let mut task = Task::new(ids.allocate());
 
         assert!(!task.is_pending());
         assert!(task.is_done());
 
-        let subtask = SubTask::new(1.0, 1.0);
+        let subtask = SubTask::new(ids.allocate(), 1.0, 1.0);
         task.push_pending(subtask);
 
         assert!(task.is_pending());
@@ -134,4 +247,95 @@ mod tests {
         assert!(!task.is_pending());
         assert!(task.is_done());
     }
+
+    #[test]
+    fn requeue() {
+        let mut ids = IdAllocator::new();
+        let mut task = Task::new(ids.allocate());
+        task.push_pending(SubTask::new(ids.allocate(), 1.0, 1.0));
+
+        let subtask = task.pop_pending().unwrap();
+        task.requeue(subtask);
+
+        assert!(task.is_pending());
+        assert_eq!(task.pop_pending(), Some(subtask));
+
+        task.push_done(subtask);
+
+        assert!(task.is_done());
+    }
+
+    #[test]
+    fn expire() {
+        let mut ids = IdAllocator::new();
+        let mut task = Task::new(ids.allocate());
+        let mut expiring = SubTask::new(ids.allocate(), 1.0, 1.0);
+        expiring.deadline = Some(Deadline::At(100.0));
+        task.push_pending(expiring);
+        task.push_pending(SubTask::new(ids.allocate(), 1.0, 1.0));
+
+        assert_eq!(task.expire(50.0), 0);
+        assert!(!task.has_expired(150.0));
+        assert_eq!(task.expire(150.0), 1);
+        assert_eq!(task.pending.len(), 1);
+
+        // the task finishes, though it is not done
+        let subtask = task.pop_pending().unwrap();
+        task.push_done(subtask);
+        assert!(task.is_finished());
+        assert!(!task.is_done());
+
+        // copies keep the expired subtasks
+        let respawned = task.respawn(&mut ids);
+        assert_eq!(respawned.pending.len(), 2);
+        assert!(!respawned.has_expired(150.0));
+
+        let mut task = Task::new(ids.allocate());
+        task.push_pending(expiring);
+        assert!(!task.has_expired(50.0));
+        assert!(task.has_expired(150.0));
+    }
+
+    #[test]
+    fn respawn() {
+        let mut ids = IdAllocator::new();
+        let mut task = Task::new(ids.allocate());
+        task.push_pending(SubTask::new(ids.allocate(), 1.0, 2.0));
+        task.push_pending(SubTask::new(ids.allocate(), 3.0, 4.0));
+
+        let subtask = task.pop_pending().unwrap();
+        assert_eq!(subtask.task_id(), Some(*task.id()));
+        task.push_done(subtask);
+
+        let respawned = task.respawn(&mut ids);
+        assert_ne!(respawned.id(), task.id());
+        assert_eq!(respawned.pending.len(), 2);
+        assert!(respawned
+            .pending
+            .iter()
+            .all(|subtask| subtask.task_id() == Some(*respawned.id()) && *subtask != task.done[0]));
+    }
+
+    #[test]
+    fn deadline() {
+        let mut ids = IdAllocator::new();
+        assert_eq!(Deadline::At(100.0).time_left(40.0), 60.0);
+        assert_eq!(Deadline::At(100.0).time_left(140.0), 0.0);
+        assert_eq!(Deadline::After(30.0).time_left(140.0), 30.0);
+        assert!(Deadline::At(100.0).has_passed(100.0));
+        assert!(!Deadline::At(100.0).has_passed(40.0));
+        assert!(!Deadline::After(30.0).has_passed(140.0));
+
+        let mut task = Task::new(ids.allocate());
+        let mut subtask = SubTask::new(ids.allocate(), 1.0, 2.0);
+        subtask.deadline = Some(Deadline::After(30.0));
+        task.push_pending(subtask);
+
+        // copies keep the deadline
+        let mut respawned = task.respawn(&mut ids);
+        assert_eq!(
+            respawned.pop_pending().unwrap().deadline,
+            Some(Deadline::After(30.0))
+        );
+    }
 }
@@ -0,0 +1,414 @@
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use log::error;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::id::Id;
+use crate::task::subtask;
+
+// what happened to a subtask once the requestor got (or gave up on) a result
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Pending,
+    Cancelled,
+    Done,
+}
+
+impl From<&subtask::Status> for Outcome {
+    fn from(status: &subtask::Status) -> Self {
+        match status {
+            subtask::Status::Pending => Outcome::Pending,
+            subtask::Status::Cancelled => Outcome::Cancelled,
+            subtask::Status::Done => Outcome::Done,
+        }
+    }
+}
+
+// market interactions between requestors and providers, as opposed to the
+// simulation events driving them
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum DomainEvent {
+    OfferSent {
+        provider_id: Id,
+        requestor_id: Id,
+        task_id: Id,
+        price: f64,
+    },
+    #[serde(rename = "subtask_assigned")]
+    SubTaskAssigned {
+        requestor_id: Id,
+        provider_id: Id,
+        subtask_id: Id,
+        bid: f64,
+    },
+    UsageReported {
+        provider_id: Id,
+        requestor_id: Id,
+        subtask_id: Id,
+        usage: f64,
+    },
+    Verified {
+        requestor_id: Id,
+        provider_id: Id,
+        subtask_id: Id,
+        outcome: Outcome,
+    },
+    RatingUpdated {
+        requestor_id: Id,
+        provider_id: Id,
+        rating: f64,
+    },
+    // `requests` is the number of tasks the ban lasts for, if not indefinite
+    Blacklisted {
+        requestor_id: Id,
+        provider_id: Id,
+        requests: Option<i64>,
+    },
+    // published once the payment reaches the provider
+    Payment {
+        requestor_id: Id,
+        provider_id: Id,
+        subtask_id: Id,
+        amount: f64,
+    },
+}
+
+impl DomainEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DomainEvent::OfferSent { .. } => "offer_sent",
+            DomainEvent::SubTaskAssigned { .. } => "subtask_assigned",
+            DomainEvent::UsageReported { .. } => "usage_reported",
+            DomainEvent::Verified { .. } => "verified",
+            DomainEvent::RatingUpdated { .. } => "rating_updated",
+            DomainEvent::Blacklisted { .. } => "blacklisted",
+            DomainEvent::Payment { .. } => "payment",
+        }
+    }
+
+    // the requestor and provider involved
+    pub fn actors(&self) -> (Id, Id) {
+        match *self {
+            DomainEvent::OfferSent {
+                requestor_id,
+                provider_id,
+                ..
+            }
+            | DomainEvent::SubTaskAssigned {
+                requestor_id,
+                provider_id,
+                ..
+            }
+            | DomainEvent::UsageReported {
+                requestor_id,
+                provider_id,
+                ..
+            }
+            | DomainEvent::Verified {
+                requestor_id,
+                provider_id,
+                ..
+            }
+            | DomainEvent::RatingUpdated {
+                requestor_id,
+                provider_id,
+                ..
+            }
+            | DomainEvent::Blacklisted {
+                requestor_id,
+                provider_id,
+                ..
+            }
+            | DomainEvent::Payment {
+                requestor_id,
+                provider_id,
+                ..
+            } => (requestor_id, provider_id),
+        }
+    }
+}
+
+// a domain event together with the simulation time it happened at
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub time: f64,
+    #[serde(flatten)]
+    pub event: DomainEvent,
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (requestor_id, provider_id) = self.event.actors();
+
+        write!(
+            f,
+            "{} at {} between R{} and P{}",
+            self.event.kind(),
+            self.time,
+            requestor_id,
+            provider_id
+        )
+    }
+}
+
+// reads a trace written by JsonLinesSink
+pub fn read_trace<R>(reader: R) -> Result<Vec<Record>, Box<dyn Error>>
+where
+    R: BufRead,
+{
+    let mut records = Vec::new();
+
+    for line in reader.lines() {
+        records.push(serde_json::from_str(&line?)?);
+    }
+
+    Ok(records)
+}
+
+// the first point at which two traces of the same run differ; either record
+// is missing if its trace ended early
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    pub index: usize,
+    pub expected: Option<Record>,
+    pub actual: Option<Record>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let describe = |record: &Option<Record>| match record {
+            Some(record) => record.to_string(),
+            None => String::from("end of trace"),
+        };
+
+        write!(
+            f,
+            "traces diverge at event {}: expected {}, got {}",
+            self.index,
+            describe(&self.expected),
+            describe(&self.actual)
+        )?;
+
+        if let (Some(expected), Some(actual)) = (&self.expected, &self.actual) {
+            if expected.event.kind() == actual.event.kind() {
+                write!(f, "\n  expected {:?}\n  got      {:?}", expected, actual)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn diff(expected: &[Record], actual: &[Record]) -> Option<Divergence> {
+    let len = expected.len().max(actual.len());
+
+    (0..len)
+        .find(|&i| expected.get(i) != actual.get(i))
+        .map(|index| Divergence {
+            index,
+            expected: expected.get(index).cloned(),
+            actual: actual.get(index).cloned(),
+        })
+}
+
+pub trait Subscriber: fmt::Debug {
+    fn notify(&mut self, time: f64, event: &DomainEvent);
+}
+
+// distributes the domain events to the subscribers; subscribers are not part
+// of the world's state, and hence are not checkpointed
+#[derive(Debug, Default)]
+pub struct Bus {
+    subscribers: Vec<Box<dyn Subscriber>>,
+}
+
+impl Bus {
+    pub fn subscribe(&mut self, subscriber: Box<dyn Subscriber>) {
+        self.subscribers.push(subscriber);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    pub fn publish(&mut self, time: f64, event: &DomainEvent) {
+        for subscriber in &mut self.subscribers {
+            subscriber.notify(time, event);
+        }
+    }
+}
+
+// keeps the domain events in memory; the records are shared with the caller
+#[derive(Debug, Default)]
+pub struct Recorder {
+    records: Rc<RefCell<Vec<Record>>>,
+}
+
+impl Recorder {
+    pub fn records(&self) -> Rc<RefCell<Vec<Record>>> {
+        self.records.clone()
+    }
+}
+
+impl Subscriber for Recorder {
+    fn notify(&mut self, time: f64, event: &DomainEvent) {
+        self.records.borrow_mut().push(Record {
+            time,
+            event: event.clone(),
+        });
+    }
+}
+
+// writes each domain event as a JSON object on a line of its own
+pub struct JsonLinesSink<W>
+where
+    W: Write,
+{
+    writer: W,
+}
+
+impl<W> JsonLinesSink<W>
+where
+    W: Write,
+{
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    fn write(&mut self, time: f64, event: &DomainEvent) -> io::Result<()> {
+        let record = Record {
+            time,
+            event: event.clone(),
+        };
+
+        serde_json::to_writer(&mut self.writer, &record)?;
+        self.writer.write_all(b"\n")
+    }
+}
+
+impl<W> fmt::Debug for JsonLinesSink<W>
+where
+    W: Write,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "JsonLinesSink")
+    }
+}
+
+impl<W> Subscriber for JsonLinesSink<W>
+where
+    W: Write,
+{
+    fn notify(&mut self, time: f64, event: &DomainEvent) {
+        if let Err(err) = self.write(time, event) {
+            error!("could not write {:?} to the trace: {}", event, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::id::IdAllocator;
+
+    #[derive(Debug, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_lines_sink() {
+        let mut ids = IdAllocator::new();
+        let buffer = Shared::default();
+        let written = buffer.0.clone();
+
+        let mut bus = Bus::default();
+        bus.subscribe(Box::new(JsonLinesSink::new(buffer)));
+
+        let event = DomainEvent::Payment {
+            requestor_id: ids.allocate(),
+            provider_id: ids.allocate(),
+            subtask_id: ids.allocate(),
+            amount: 0.5,
+        };
+        bus.publish(10.0, &event);
+        bus.publish(20.0, &event);
+
+        let written = String::from_utf8(written.borrow().clone()).unwrap();
+        let records: Vec<Record> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].time, 10.0);
+        assert_eq!(records[1].event, event);
+        assert!(written.starts_with(r#"{"time":10.0,"event":"payment""#));
+
+        let event = DomainEvent::SubTaskAssigned {
+            requestor_id: ids.allocate(),
+            provider_id: ids.allocate(),
+            subtask_id: ids.allocate(),
+            bid: 0.1,
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.starts_with(r#"{"event":"subtask_assigned""#));
+    }
+
+    #[test]
+    fn diff_traces() {
+        let mut ids = IdAllocator::new();
+        let (requestor_id, provider_id) = (ids.allocate(), ids.allocate());
+        let record = |time, rating| Record {
+            time,
+            event: DomainEvent::RatingUpdated {
+                requestor_id,
+                provider_id,
+                rating,
+            },
+        };
+
+        let expected = vec![record(1.0, 0.5), record(2.0, 0.6), record(3.0, 0.7)];
+        assert_eq!(diff(&expected, &expected), None);
+
+        let mut actual = expected.clone();
+        actual[1] = record(2.0, 0.65);
+        let divergence = diff(&expected, &actual).unwrap();
+        assert_eq!(divergence.index, 1);
+        assert_eq!(divergence.expected, Some(record(2.0, 0.6)));
+        assert_eq!(divergence.actual, Some(record(2.0, 0.65)));
+
+        // ids are allocated per world, hence differing ones count as well
+        let other_provider_id = ids.allocate();
+        let mut actual = expected.clone();
+        actual[2] = Record {
+            time: 3.0,
+            event: DomainEvent::RatingUpdated {
+                requestor_id,
+                provider_id: other_provider_id,
+                rating: 0.7,
+            },
+        };
+        assert_eq!(diff(&expected, &actual).unwrap().index, 2);
+
+        // a trace cut short diverges where it ends
+        let divergence = diff(&expected, &expected[..2]).unwrap();
+        assert_eq!(divergence.index, 2);
+        assert_eq!(divergence.actual, None);
+    }
+}
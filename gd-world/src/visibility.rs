@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use rand::seq::SliceRandom;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::id::Id;
+
+// which providers each requestor can see, and hence collect offers from
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    // every provider in the market
+    #[default]
+    Full,
+    // `k` providers drawn afresh for each advertisement
+    RandomSubset(usize),
+    // static overlay; each actor joining the market is linked to `degree`
+    // random actors on the other side
+    Overlay(usize),
+    // each requestor starts off knowing `initial` random providers, and every
+    // `interval` seconds learns of up to `fanout` more from a random peer; a
+    // provider joining the market announces itself to `fanout` requestors
+    Gossip {
+        initial: usize,
+        fanout: usize,
+        interval: f64,
+    },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MarketView {
+    visibility: Visibility,
+    // providers known to each requestor; unused by full and random subset
+    // visibility
+    views: BTreeMap<Id, BTreeSet<Id>>,
+}
+
+impl MarketView {
+    pub fn new(visibility: Visibility) -> Self {
+        Self {
+            visibility,
+            views: BTreeMap::new(),
+        }
+    }
+
+    pub fn visibility(&self) -> &Visibility {
+        &self.visibility
+    }
+
+    pub fn gossip_interval(&self) -> Option<f64> {
+        match self.visibility {
+            Visibility::Gossip { interval, .. } => Some(interval),
+            _ => None,
+        }
+    }
+
+    pub fn requestor_joined<Rng>(&mut self, requestor_id: Id, providers: &[Id], rng: &mut Rng)
+    where
+        Rng: rand::Rng,
+    {
+        let count = match self.visibility {
+            Visibility::Overlay(degree) => degree,
+            Visibility::Gossip { initial, .. } => initial,
+            _ => return,
+        };
+
+        let view = providers.choose_multiple(rng, count).cloned().collect();
+        self.views.insert(requestor_id, view);
+    }
+
+    pub fn provider_joined<Rng>(&mut self, provider_id: Id, requestors: &[Id], rng: &mut Rng)
+    where
+        Rng: rand::Rng,
+    {
+        let count = match self.visibility {
+            Visibility::Overlay(degree) => degree,
+            Visibility::Gossip { fanout, .. } => fanout,
+            _ => return,
+        };
+
+        for requestor_id in requestors.choose_multiple(rng, count) {
+            self.views
+                .entry(*requestor_id)
+                .or_default()
+                .insert(provider_id);
+        }
+    }
+
+    pub fn provider_left(&mut self, provider_id: Id) {
+        for view in self.views.values_mut() {
+            view.remove(&provider_id);
+        }
+    }
+
+    // one round of gossip; each requestor in turn pulls from a random peer
+    pub fn gossip<Rng>(&mut self, rng: &mut Rng)
+    where
+        Rng: rand::Rng,
+    {
+        let fanout = match self.visibility {
+            Visibility::Gossip { fanout, .. } => fanout,
+            _ => return,
+        };
+
+        let requestors: Vec<Id> = self.views.keys().cloned().collect();
+
+        for &requestor_id in &requestors {
+            let peer_id = match requestors.choose(rng) {
+                Some(&peer_id) if peer_id != requestor_id => peer_id,
+                _ => continue,
+            };
+
+            let peer_view: Vec<Id> = self.views[&peer_id].iter().cloned().collect();
+            let learnt: Vec<Id> = peer_view.choose_multiple(rng, fanout).cloned().collect();
+
+            self.views
+                .get_mut(&requestor_id)
+                .expect("view not found")
+                .extend(learnt);
+        }
+    }
+
+    // the providers, out of those in the market, visible to the requestor
+    pub fn visible<Rng>(&self, requestor_id: Id, providers: Vec<Id>, rng: &mut Rng) -> Vec<Id>
+    where
+        Rng: rand::Rng,
+    {
+        match self.visibility {
+            Visibility::Full => providers,
+            Visibility::RandomSubset(k) => {
+                let mut visible: Vec<Id> = providers.choose_multiple(rng, k).cloned().collect();
+                visible.sort();
+                visible
+            }
+            _ => match self.views.get(&requestor_id) {
+                Some(view) => providers
+                    .into_iter()
+                    .filter(|id| view.contains(id))
+                    .collect(),
+                None => Vec::new(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+
+    use crate::id::IdAllocator;
+    use crate::rng::SimRng;
+
+    fn allocate(ids: &mut IdAllocator, count: usize) -> Vec<Id> {
+        (0..count).map(|_| ids.allocate()).collect()
+    }
+
+    #[test]
+    fn random_subset() {
+        let mut ids = IdAllocator::new();
+        let mut rng = SimRng::seed_from_u64(0);
+        let view = MarketView::new(Visibility::RandomSubset(3));
+        let providers = allocate(&mut ids, 10);
+
+        let visible = view.visible(ids.allocate(), providers.clone(), &mut rng);
+        assert_eq!(visible.len(), 3);
+        assert!(visible.windows(2).all(|w| w[0] < w[1]));
+        assert!(visible.iter().all(|id| providers.contains(id)));
+
+        let visible = view.visible(ids.allocate(), providers[..2].to_vec(), &mut rng);
+        assert_eq!(visible.len(), 2);
+    }
+
+    #[test]
+    fn overlay() {
+        let mut ids = IdAllocator::new();
+        let mut rng = SimRng::seed_from_u64(0);
+        let mut view = MarketView::new(Visibility::Overlay(2));
+        let requestors = allocate(&mut ids, 3);
+        let providers = allocate(&mut ids, 10);
+
+        for &requestor_id in &requestors {
+            view.requestor_joined(requestor_id, &providers, &mut rng);
+        }
+
+        for &requestor_id in &requestors {
+            assert_eq!(
+                view.visible(requestor_id, providers.clone(), &mut rng)
+                    .len(),
+                2
+            );
+        }
+
+        // a newcomer becomes visible to `degree` requestors
+        let newcomer = ids.allocate();
+        view.provider_joined(newcomer, &requestors, &mut rng);
+
+        let mut providers = providers;
+        providers.push(newcomer);
+        let count = requestors
+            .iter()
+            .filter(|&&id| {
+                view.visible(id, providers.clone(), &mut rng)
+                    .contains(&newcomer)
+            })
+            .count();
+        assert_eq!(count, 2);
+
+        view.provider_left(newcomer);
+        assert!(view.views.values().all(|view| !view.contains(&newcomer)));
+    }
+
+    #[test]
+    fn gossip() {
+        let mut ids = IdAllocator::new();
+        let mut rng = SimRng::seed_from_u64(0);
+        let mut view = MarketView::new(Visibility::Gossip {
+            initial: 1,
+            fanout: 2,
+            interval: 60.0,
+        });
+        let requestors = allocate(&mut ids, 5);
+        let providers = allocate(&mut ids, 20);
+
+        for &requestor_id in &requestors {
+            view.requestor_joined(requestor_id, &providers, &mut rng);
+        }
+
+        let known = |view: &MarketView| view.views.values().map(BTreeSet::len).sum::<usize>();
+        assert_eq!(known(&view), 5);
+
+        // views only ever grow through gossip
+        let mut previous = known(&view);
+        for _ in 0..10 {
+            view.gossip(&mut rng);
+            assert!(known(&view) >= previous);
+            previous = known(&view);
+        }
+        assert!(previous > 5);
+    }
+}
@@ -1,51 +1,263 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-use gd_engine::Engine;
-use log::debug;
+use gd_engine::{Engine, EngineObserver, Priority, Profile, QueueKind, Time};
+use log::{debug, error};
 use rand::seq::SliceRandom;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_derive::{Deserialize, Serialize};
 
-use crate::id::Id;
+use crate::id::{Id, IdAllocator};
+use crate::metrics::Sample;
 use crate::provider;
 use crate::provider::Provider;
 use crate::requestor;
-use crate::requestor::Requestor;
+use crate::requestor::{DefenceMechanismType, Requestor};
+use crate::scenario::{LatencyModel, Message, ProviderSource, RequestorSource};
+use crate::settlement::{Payment, Settlement, SettlementModel};
 use crate::task::SubTask;
+use crate::trace::{Bus, DomainEvent, Subscriber};
+use crate::visibility::{MarketView, Visibility};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum Event {
-    TaskAdvertisement(Id),
+    // requestor and task ids
+    TaskAdvertisement(Id, Id),
     SubTaskComputed(SubTask, Id, Id, f64),
     SubTaskBudgetExceeded(SubTask, Id, Id),
+    // the provider crashed while computing the subtask
+    SubTaskDropped(SubTask, Id, Id),
+    // the requestor gave up waiting for the provider to report back
+    SubTaskOverdue(SubTask, Id, Id),
+    // the subtask's deadline passed before the provider reported back
+    SubTaskTimedOut(SubTask, Id, Id),
+    // messages between requestors and providers; cf. LatencyModel
+    OffersReceived(Id, Id, Vec<(Id, f64)>),
+    SubTaskDelivered(SubTask, Id, Id, f64),
+    SubTaskReported(SubTask, Id, Id, f64, Option<f64>),
+    PaymentDelivered(SubTask, Id, Id, Option<f64>),
+    // the requestor withdrew the computed subtask in lieu of paying for it
+    SubTaskCancelled(SubTask, Id, Id),
+    // payment handed over for settlement after being held back
+    PaymentSubmitted(SubTask, Id, Id, f64),
+    // requestor id; the batch of payments is settled in a single transaction
+    BatchClosed(Id),
+    // index of the provider source the new provider is sampled from
+    ProviderArrival(usize),
+    ProviderDeparture(Id),
+    // index of the requestor source the new requestor is sampled from
+    RequestorArrival(usize),
+    RequestorDeparture(Id),
+    Gossip,
+    Sample,
+    Checkpoint,
 }
 
-#[derive(Debug)]
-pub struct World<Rng>
+impl Event {
+    pub fn priority(&self) -> Priority {
+        match self {
+            // (re)advertisements come last so that all results delivered
+            // at the same instant are accounted for first
+            Event::TaskAdvertisement(..) => Priority(1),
+            _ => Priority::default(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::TaskAdvertisement(..) => "task_advertisement",
+            Event::SubTaskComputed(..) => "subtask_computed",
+            Event::SubTaskBudgetExceeded(..) => "subtask_budget_exceeded",
+            Event::SubTaskDropped(..) => "subtask_dropped",
+            Event::SubTaskOverdue(..) => "subtask_overdue",
+            Event::SubTaskTimedOut(..) => "subtask_timed_out",
+            Event::OffersReceived(..) => "offers_received",
+            Event::SubTaskDelivered(..) => "subtask_delivered",
+            Event::SubTaskReported(..) => "subtask_reported",
+            Event::PaymentDelivered(..) => "payment_delivered",
+            Event::SubTaskCancelled(..) => "subtask_cancelled",
+            Event::PaymentSubmitted(..) => "payment_submitted",
+            Event::BatchClosed(_) => "batch_closed",
+            Event::ProviderArrival(_) => "provider_arrival",
+            Event::ProviderDeparture(_) => "provider_departure",
+            Event::RequestorArrival(_) => "requestor_arrival",
+            Event::RequestorDeparture(_) => "requestor_departure",
+            Event::Gossip => "gossip",
+            Event::Sample => "sample",
+            Event::Checkpoint => "checkpoint",
+        }
+    }
+}
+
+type SaveCheckpoint<W> = fn(&W, &Path) -> Result<(), Box<dyn Error>>;
+
+// conditions, besides the time horizon, on which a run ends early; checked
+// after every event
+pub enum StopCondition<W> {
+    // every requestor with a non-repeating task queue has drained it and has
+    // no task left in progress
+    WorkloadDone,
+    // this many events have been handled since the start of the simulation
+    MaxEvents(u64),
+    Predicate(Box<dyn Fn(&W) -> bool>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    // the horizon was reached, or there were no events left before it
+    Horizon,
+    WorkloadDone,
+    MaxEvents,
+    Predicate,
+}
+
+impl StopReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StopReason::Horizon => "horizon",
+            StopReason::WorkloadDone => "workload_done",
+            StopReason::MaxEvents => "max_events",
+            StopReason::Predicate => "predicate",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Checkpoints {
+    interval: f64,
+    path: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "Rng: Serialize, T: Serialize",
+    deserialize = "Rng: DeserializeOwned, T: DeserializeOwned"
+))]
+pub struct World<Rng, T = f64>
 where
     Rng: rand::Rng + 'static,
+    T: Time,
 {
     rng: Rng,
-    engine: Engine<Event>,
-    requestors: HashMap<Id, Requestor>,
-    providers: HashMap<Id, Box<dyn Provider<Rng = Rng>>>,
+    engine: Engine<Event, T>,
+    // source of the ids of the actors, tasks and subtasks of this world
+    ids: IdAllocator,
+    requestors: BTreeMap<Id, Requestor>,
+    // requestors which have left the market; they still settle the subtasks
+    // already handed out, but advertise no more
+    departed_requestors: BTreeSet<Id>,
+    requestor_sources: Vec<(RequestorSource, DefenceMechanismType)>,
+    #[serde(with = "provider::boxed")]
+    providers: BTreeMap<Id, Box<dyn Provider<Rng = Rng>>>,
+    // providers which have left the market; kept around for the stats
+    #[serde(with = "provider::boxed")]
+    departed_providers: BTreeMap<Id, Box<dyn Provider<Rng = Rng>>>,
+    provider_sources: Vec<ProviderSource>,
+    latency: LatencyModel,
+    // payments are delivered as messages unless settled in transactions
+    settlement: Option<Settlement>,
+    market_view: MarketView,
+    has_started: bool,
+    num_events: u64,
+    // interval between consecutive samples of the market state, if sampled
+    sampling: Option<f64>,
+    samples: Vec<Sample>,
+    checkpoints: Option<Checkpoints>,
+    // set up by enable_checkpoints and load_checkpoint; this way, checkpointing
+    // does not require a serializable Rng elsewhere
+    #[serde(skip)]
+    save_checkpoint: Option<SaveCheckpoint<Self>>,
+    #[serde(skip)]
+    bus: Bus,
 }
 
-impl<Rng> World<Rng>
+impl<Rng, T> World<Rng, T>
 where
     Rng: rand::Rng + 'static,
+    T: Time,
 {
     pub fn new(rng: Rng) -> Self {
+        Self::with_event_queue(rng, QueueKind::default())
+    }
+
+    pub fn with_event_queue(rng: Rng, kind: QueueKind) -> Self {
         Self {
             rng,
-            engine: Engine::new(),
-            requestors: HashMap::new(),
-            providers: HashMap::new(),
+            engine: Engine::with_queue(kind),
+            ids: IdAllocator::new(),
+            requestors: BTreeMap::new(),
+            departed_requestors: BTreeSet::new(),
+            requestor_sources: Vec::new(),
+            providers: BTreeMap::new(),
+            departed_providers: BTreeMap::new(),
+            provider_sources: Vec::new(),
+            latency: LatencyModel::default(),
+            settlement: None,
+            market_view: MarketView::default(),
+            has_started: false,
+            num_events: 0,
+            sampling: None,
+            samples: Vec::new(),
+            checkpoints: None,
+            save_checkpoint: None,
+            bus: Bus::default(),
         }
     }
 
+    pub fn add_engine_observer(&mut self, observer: Box<dyn EngineObserver<Event, T>>) {
+        self.engine.add_observer(observer);
+    }
+
+    pub fn subscribe(&mut self, subscriber: Box<dyn Subscriber>) {
+        self.bus.subscribe(subscriber);
+    }
+
+    pub fn enable_profiling(&mut self) {
+        self.engine.enable_profiling(Event::kind);
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.engine.profile()
+    }
+
+    // actors created outside the world need ids from the same allocator
+    pub fn ids_mut(&mut self) -> &mut IdAllocator {
+        &mut self.ids
+    }
+
+    pub fn set_ids(&mut self, ids: IdAllocator) {
+        self.ids = ids;
+    }
+
+    pub fn set_latency(&mut self, latency: LatencyModel) {
+        self.latency = latency;
+    }
+
+    pub fn set_settlement(&mut self, model: SettlementModel) {
+        self.settlement = Some(Settlement::new(model));
+    }
+
+    pub fn set_visibility(&mut self, visibility: Visibility) {
+        self.market_view = MarketView::new(visibility);
+    }
+
     pub fn push_requestor(&mut self, requestor: Requestor) {
         debug!("W:adding {}", requestor);
 
-        self.requestors.insert(*requestor.id(), requestor);
+        let requestor_id = *requestor.id();
+        self.requestors.insert(requestor_id, requestor);
+
+        // those present from the start are linked up once the simulation starts
+        if self.has_started {
+            let providers: Vec<Id> = self.providers.keys().cloned().collect();
+            self.market_view
+                .requestor_joined(requestor_id, &providers, &mut self.rng);
+        }
     }
 
     pub fn append_requestors<It>(&mut self, requestors: It)
@@ -57,10 +269,37 @@ where
         }
     }
 
+    // keeps sampling new requestors from the source throughout the simulation,
+    // as long as the source specifies an arrival process
+    pub fn push_requestor_source(
+        &mut self,
+        source: RequestorSource,
+        defence_mechanism_type: DefenceMechanismType,
+    ) {
+        self.requestor_sources
+            .push((source, defence_mechanism_type));
+
+        if self.has_started {
+            self.schedule_requestor_arrival(self.requestor_sources.len() - 1);
+        }
+    }
+
+    pub fn schedule_requestor_departure(&mut self, requestor_id: Id, after: f64) {
+        self.engine
+            .schedule(T::from_secs(after), Event::RequestorDeparture(requestor_id));
+    }
+
     pub fn push_provider(&mut self, provider: Box<dyn Provider<Rng = Rng>>) {
         debug!("W:adding {}", provider);
 
-        self.providers.insert(*provider.id(), provider);
+        let provider_id = *provider.id();
+        self.providers.insert(provider_id, provider);
+
+        if self.has_started {
+            let requestors: Vec<Id> = self.requestors.keys().cloned().collect();
+            self.market_view
+                .provider_joined(provider_id, &requestors, &mut self.rng);
+        }
     }
 
     pub fn append_providers<It>(&mut self, providers: It)
@@ -72,108 +311,825 @@ where
         }
     }
 
-    pub fn into_stats(mut self, run_id: u64) -> (Vec<requestor::Stats>, Vec<provider::Stats>) {
+    // keeps sampling new providers from the source throughout the simulation,
+    // as long as the source specifies an arrival process
+    pub fn push_provider_source(&mut self, source: ProviderSource) {
+        self.provider_sources.push(source);
+
+        if self.has_started {
+            self.schedule_provider_arrival(self.provider_sources.len() - 1);
+        }
+    }
+
+    pub fn schedule_provider_departure(&mut self, provider_id: Id, after: f64) {
+        self.engine
+            .schedule(T::from_secs(after), Event::ProviderDeparture(provider_id));
+    }
+
+    pub fn into_stats(self, run_id: u64) -> (Vec<requestor::Stats>, Vec<provider::Stats>) {
+        let mut providers = self.providers;
+        providers.extend(self.departed_providers);
+
         (
             self.requestors
-                .drain()
-                .map(|(_, requestor)| requestor.into_stats(run_id))
+                .into_values()
+                .map(|requestor| requestor.into_stats(run_id))
                 .collect(),
-            self.providers
-                .drain()
-                .map(|(_, provider)| provider.into_stats(run_id))
+            providers
+                .into_values()
+                .map(|provider| provider.into_stats(run_id))
                 .collect(),
         )
     }
 
+    // samples the market state every `interval` seconds, starting right away
+    pub fn enable_sampling(&mut self, interval: f64) {
+        // a world resumed from a checkpoint may be sampling already
+        if self.sampling.replace(interval).is_none() {
+            self.engine.schedule(T::from_secs(0.0), Event::Sample);
+        }
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    pub fn enable_checkpoints<P>(&mut self, interval: f64, path: P)
+    where
+        Rng: Serialize + DeserializeOwned,
+        T: Serialize + DeserializeOwned,
+        P: Into<PathBuf>,
+    {
+        let checkpoints = Checkpoints {
+            interval,
+            path: path.into(),
+        };
+        self.save_checkpoint = Some(Self::save_checkpoint_to);
+
+        // a world resumed from a checkpoint has the next one pending already
+        if self.checkpoints.replace(checkpoints).is_none() {
+            self.engine
+                .schedule(T::from_secs(interval), Event::Checkpoint);
+        }
+    }
+
+    pub fn save_checkpoint<P>(&self, path: P) -> Result<(), Box<dyn Error>>
+    where
+        Rng: Serialize,
+        T: Serialize,
+        P: AsRef<Path>,
+    {
+        self.save_checkpoint_to(path.as_ref())
+    }
+
+    pub fn load_checkpoint<P>(path: P) -> Result<Self, Box<dyn Error>>
+    where
+        Rng: Serialize + DeserializeOwned,
+        T: Serialize + DeserializeOwned,
+        P: AsRef<Path>,
+    {
+        let mut world: Self = bincode::deserialize_from(BufReader::new(File::open(path)?))?;
+        world.save_checkpoint = Some(Self::save_checkpoint_to);
+
+        Ok(world)
+    }
+
+    fn save_checkpoint_to(&self, path: &Path) -> Result<(), Box<dyn Error>>
+    where
+        Rng: Serialize,
+        T: Serialize,
+    {
+        // write to a temporary file first so that a crash mid-write does not
+        // clobber the previous checkpoint
+        let tmp_path = path.with_extension("tmp");
+
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        bincode::serialize_into(&mut writer, self)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        fs::rename(tmp_path, path)?;
+
+        Ok(())
+    }
+
+    pub fn now(&self) -> f64 {
+        self.engine.now().as_secs()
+    }
+
+    // number of events handled since the start of the simulation, leaving
+    // out samples and checkpoints
+    pub fn num_events(&self) -> u64 {
+        self.num_events
+    }
+
+    pub fn requestors(&self) -> impl Iterator<Item = &Requestor> {
+        self.requestors.values()
+    }
+
+    // whether the finite workload of the market has been computed; never true
+    // if every requestor repeats its tasks
+    pub fn is_workload_done(&self) -> bool {
+        let mut finite = self
+            .requestors
+            .iter()
+            .filter(|(id, requestor)| {
+                !requestor.task_queue().repeating && !self.departed_requestors.contains(id)
+            })
+            .peekable();
+
+        finite.peek().is_some() && finite.all(|(_, requestor)| requestor.is_done())
+    }
+
     pub fn run(&mut self, until: f64) {
-        self.started();
+        self.run_with(until, &[]);
+    }
 
-        while let Some(event) = self.engine.pop() {
-            let now = self.engine.now();
+    // runs until the horizon, or until any of the conditions holds
+    pub fn run_with(&mut self, until: f64, conditions: &[StopCondition<Self>]) -> StopReason {
+        if !self.has_started {
+            self.started();
+        }
 
-            if until < now {
-                break;
-            }
+        let until = T::from_secs(until);
+        let mut reason = StopReason::Horizon;
 
-            debug!("W:now = {}", now);
+        while let Some(event) = self.engine.pop_until(until) {
+            debug!("W:now = {}", self.engine.now().as_secs());
             self.handle(event);
+
+            if let Some(stop) = self.check(conditions) {
+                reason = stop;
+                break;
+            }
         }
 
         self.stopped();
+
+        reason
     }
 
-    fn handle_advertise(&mut self, requestor_id: Id) {
-        let requestor = self
+    // dispatches a single event; returns false once there are no events left
+    pub fn step(&mut self) -> bool {
+        if !self.has_started {
+            self.started();
+        }
+
+        match self.engine.pop() {
+            Some(event) => {
+                debug!("W:now = {}", self.engine.now().as_secs());
+                self.handle(event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn check(&self, conditions: &[StopCondition<Self>]) -> Option<StopReason> {
+        conditions.iter().find_map(|condition| match condition {
+            StopCondition::WorkloadDone if self.is_workload_done() => {
+                Some(StopReason::WorkloadDone)
+            }
+            StopCondition::MaxEvents(max_events) if self.num_events >= *max_events => {
+                Some(StopReason::MaxEvents)
+            }
+            StopCondition::Predicate(predicate) if predicate(self) => Some(StopReason::Predicate),
+            _ => None,
+        })
+    }
+
+    // delivers the message after a delay drawn from the latency model, or
+    // right away if the model does not delay messages of this type
+    fn send(&mut self, message: Message, event: Event) {
+        self.send_after(0.0, message, event);
+    }
+
+    // like send, but the sender holds the message back for `after` seconds
+    fn send_after(&mut self, after: f64, message: Message, event: Event) {
+        match self.latency.sample(message, &mut self.rng) {
+            Some(delay) => {
+                self.engine.schedule(T::from_secs(after + delay), event);
+            }
+            None if after > 0.0 => {
+                self.engine.schedule(T::from_secs(after), event);
+            }
+            None => self.handle_now(event),
+        }
+    }
+
+    // handles the event right away rather than through the engine's queue
+    fn handle_now(&mut self, event: Event) {
+        self.engine.record_dispatch(&event);
+        self.handle(event);
+    }
+
+    fn publish(&mut self, event: DomainEvent) {
+        if !self.bus.is_empty() {
+            self.bus.publish(self.engine.now().as_secs(), &event);
+        }
+    }
+
+    // passes on what the requestor's been up to, e.g., verifying subtasks
+    fn publish_requestor_events(&mut self, requestor_id: Id) {
+        let events = self
             .requestors
             .get_mut(&requestor_id)
-            .expect("requestor not found");
+            .expect("requestor not found")
+            .take_events();
+
+        for event in events {
+            self.publish(event);
+        }
+    }
+
+    fn handle_advertise(&mut self, requestor_id: Id, task_id: Id) {
+        if self.departed_requestors.contains(&requestor_id) {
+            return;
+        }
+
+        // the task may have been computed in the meantime
+        if !self.requestors[&requestor_id].has_task(task_id) {
+            return;
+        }
+
+        // collect offers from the providers visible to the requestor
+        let providers: Vec<Id> = self.providers.keys().cloned().collect();
+        let visible = self
+            .market_view
+            .visible(requestor_id, providers, &mut self.rng);
 
-        // collect offers
         let mut bids = Vec::new();
-        for (&id, provider) in &mut self.providers {
+        for id in visible {
+            let provider = self.providers.get_mut(&id).expect("provider not found");
+
+            // one offer per free slot
             if let Some(bid) = provider.send_offer() {
-                bids.push((id, bid));
+                for _ in 0..provider.num_free_slots() {
+                    bids.push((id, bid));
+                }
             }
         }
 
-        // select offers
-        for (provider_id, subtask, bid) in requestor.select_offers(bids) {
-            let provider = self
-                .providers
-                .get_mut(&provider_id)
-                .expect("provider not found");
+        for &(provider_id, price) in &bids {
+            self.publish(DomainEvent::OfferSent {
+                provider_id,
+                requestor_id,
+                task_id,
+                price,
+            });
+        }
 
-            provider.receive_subtask(&mut self.engine, &mut self.rng, &subtask, requestor_id, bid);
+        // the requestor decides once the slowest offer has arrived
+        let mut delay = None;
+        for _ in 0..bids.len() {
+            if let Some(latency) = self.latency.sample(Message::Offer, &mut self.rng) {
+                delay = Some(delay.map_or(latency, |delay: f64| delay.max(latency)));
+            }
+        }
+
+        let event = Event::OffersReceived(requestor_id, task_id, bids);
+        match delay {
+            Some(delay) => {
+                self.engine.schedule(T::from_secs(delay), event);
+            }
+            None => self.handle_now(event),
         }
     }
 
-    fn handle_compute(&mut self, subtask: SubTask, requestor_id: Id, provider_id: Id, bid: f64) {
+    fn handle_offers(&mut self, requestor_id: Id, task_id: Id, bids: Vec<(Id, f64)>) {
+        if self.departed_requestors.contains(&requestor_id) {
+            return;
+        }
+
+        let requestor = self
+            .requestors
+            .get_mut(&requestor_id)
+            .expect("requestor not found");
+
+        if !requestor.has_task(task_id) {
+            return;
+        }
+
+        // select offers
+        let now = self.engine.now().as_secs();
+        let latency = self.latency.round_trip();
+        for (provider_id, subtask, bid) in requestor.select_offers(now, task_id, bids) {
+            self.requestors
+                .get_mut(&requestor_id)
+                .expect("requestor not found")
+                .await_reply(&mut self.engine, &subtask, provider_id, bid, latency);
+
+            self.publish(DomainEvent::SubTaskAssigned {
+                requestor_id,
+                provider_id,
+                subtask_id: *subtask.id(),
+                bid,
+            });
+
+            self.send(
+                Message::SubTask,
+                Event::SubTaskDelivered(subtask, requestor_id, provider_id, bid),
+            );
+        }
+
+        // the last subtasks may have expired in the meantime
         let requestor = self
             .requestors
             .get_mut(&requestor_id)
             .expect("requestor not found");
 
+        if requestor.has_task(task_id) {
+            requestor.complete_task(task_id);
+
+            if !requestor.has_task(task_id) {
+                self.publish_requestor_events(requestor_id);
+                self.schedule_advertise();
+            }
+        }
+    }
+
+    fn handle_delivery(&mut self, subtask: SubTask, requestor_id: Id, provider_id: Id, bid: f64) {
+        // the deadline may have passed while the subtask was on its way, in
+        // which case the requestor has withdrawn it already
+        if !self.requestors[&requestor_id].is_awaiting(&subtask, provider_id) {
+            debug!(
+                "W:P{} discarded {} of R{} withdrawn in transit",
+                provider_id, subtask, requestor_id
+            );
+
+            return;
+        }
+
+        match self.providers.get_mut(&provider_id) {
+            // the offer may have gone stale while the subtask was on its way
+            Some(provider) if provider.num_free_slots() > 0 => provider.receive_subtask(
+                &mut self.engine,
+                &mut self.rng,
+                &subtask,
+                requestor_id,
+                bid,
+            ),
+            _ => {
+                debug!(
+                    "W:P{} rejected {} of R{}",
+                    provider_id, subtask, requestor_id
+                );
+
+                self.send(
+                    Message::Result,
+                    Event::SubTaskReported(subtask, requestor_id, provider_id, bid, None),
+                );
+            }
+        }
+    }
+
+    fn handle_compute(&mut self, subtask: SubTask, requestor_id: Id, provider_id: Id, bid: f64) {
         let provider = self
             .providers
             .get_mut(&provider_id)
             .expect("provider not found");
 
-        provider.finish_computing(self.engine.now(), &subtask, requestor_id);
+        provider.finish_computing(self.engine.now().as_secs(), &subtask, requestor_id);
         let reported_usage = provider.report_usage(&mut self.rng, &subtask, bid);
-        requestor.verify_subtask(&subtask, provider_id, Some(reported_usage));
-        let payment = requestor.send_payment(&subtask, provider_id, bid, reported_usage);
-        provider.receive_payment(&subtask, requestor_id, payment);
-        requestor.complete_task();
+        provider.bill(&subtask, requestor_id, reported_usage * bid);
 
-        self.schedule_advertise();
+        self.publish(DomainEvent::UsageReported {
+            provider_id,
+            requestor_id,
+            subtask_id: *subtask.id(),
+            usage: reported_usage,
+        });
+
+        self.send(
+            Message::Result,
+            Event::SubTaskReported(
+                subtask,
+                requestor_id,
+                provider_id,
+                bid,
+                Some(reported_usage),
+            ),
+        );
     }
 
     fn handle_budget_exceeded(&mut self, subtask: SubTask, requestor_id: Id, provider_id: Id) {
-        let requestor = self
-            .requestors
-            .get_mut(&requestor_id)
-            .expect("requestor not found");
+        let provider = self
+            .providers
+            .get_mut(&provider_id)
+            .expect("provider not found");
+
+        provider.cancel_computing(self.engine.now().as_secs(), &subtask, requestor_id);
+
+        self.send(
+            Message::Result,
+            Event::SubTaskReported(subtask, requestor_id, provider_id, 0.0, None),
+        );
+    }
 
+    fn handle_dropped(&mut self, subtask: SubTask, requestor_id: Id, provider_id: Id) {
         let provider = self
             .providers
             .get_mut(&provider_id)
             .expect("provider not found");
 
-        provider.cancel_computing(self.engine.now(), &subtask, requestor_id);
-        requestor.verify_subtask(&subtask, provider_id, None);
+        provider.crash(self.engine.now().as_secs(), &subtask, requestor_id);
+    }
+
+    fn handle_overdue(&mut self, subtask: SubTask, requestor_id: Id, provider_id: Id) {
+        let requestor = self
+            .requestors
+            .get_mut(&requestor_id)
+            .expect("requestor not found");
+
+        if requestor.notify_overdue(&subtask, provider_id) {
+            requestor.complete_task(subtask.task_id().expect("task not found"));
+            self.publish_requestor_events(requestor_id);
+            self.schedule_advertise();
+        }
+    }
+
+    fn handle_timed_out(&mut self, subtask: SubTask, requestor_id: Id, provider_id: Id) {
+        let requestor = self
+            .requestors
+            .get_mut(&requestor_id)
+            .expect("requestor not found");
+
+        if !requestor.notify_timed_out(&subtask, provider_id) {
+            return;
+        }
+
+        requestor.complete_task(subtask.task_id().expect("task not found"));
+        self.publish_requestor_events(requestor_id);
+
+        // the provider is told to stop computing right away; it may have left
+        // the market or crashed in the meantime though
+        if let Some(provider) = self.providers.get_mut(&provider_id) {
+            provider.time_out(&mut self.engine, &subtask, requestor_id);
+        }
+
+        self.schedule_advertise();
+    }
+
+    fn handle_report(
+        &mut self,
+        subtask: SubTask,
+        requestor_id: Id,
+        provider_id: Id,
+        bid: f64,
+        reported_usage: Option<f64>,
+    ) {
+        let requestor = self
+            .requestors
+            .get_mut(&requestor_id)
+            .expect("requestor not found");
+
+        if !requestor.receive_reply(&mut self.engine, &subtask, provider_id) {
+            // the result came too late, hence the provider will never be paid
+            // for it; it may have left the market in the meantime
+            if reported_usage.is_some() {
+                let provider = match self.providers.get_mut(&provider_id) {
+                    Some(provider) => provider,
+                    None => self
+                        .departed_providers
+                        .get_mut(&provider_id)
+                        .expect("provider not found"),
+                };
+
+                provider.write_off(&subtask, requestor_id);
+            }
+
+            return;
+        }
+
+        requestor.verify_subtask(&subtask, provider_id, reported_usage);
+
+        if let Some(reported_usage) = reported_usage {
+            let payment = requestor.send_payment(&subtask, provider_id, bid, reported_usage);
+            let delay = requestor.payment_delay();
+            let cancelled = requestor.cancels_after_compute();
+
+            match payment {
+                _ if cancelled => self.send_after(
+                    delay,
+                    Message::Payment,
+                    Event::SubTaskCancelled(subtask, requestor_id, provider_id),
+                ),
+                Some(amount) if self.settlement.is_some() && delay > 0.0 => {
+                    let event = Event::PaymentSubmitted(subtask, requestor_id, provider_id, amount);
+                    self.engine.schedule(T::from_secs(delay), event);
+                }
+                Some(amount) if self.settlement.is_some() => {
+                    self.handle_submitted(subtask, requestor_id, provider_id, amount)
+                }
+                _ => self.send_after(
+                    delay,
+                    Message::Payment,
+                    Event::PaymentDelivered(subtask, requestor_id, provider_id, payment),
+                ),
+            }
+        }
+
+        // with redundancy, the task may be done even if this replica failed
+        self.requestors
+            .get_mut(&requestor_id)
+            .expect("requestor not found")
+            .complete_task(subtask.task_id().expect("task not found"));
+
+        self.publish_requestor_events(requestor_id);
+        self.schedule_advertise();
+    }
+
+    fn handle_payment(
+        &mut self,
+        subtask: SubTask,
+        requestor_id: Id,
+        provider_id: Id,
+        payment: Option<f64>,
+    ) {
+        // the provider may have left the market in the meantime
+        let provider = match self.providers.get_mut(&provider_id) {
+            Some(provider) => provider,
+            None => self
+                .departed_providers
+                .get_mut(&provider_id)
+                .expect("provider not found"),
+        };
+
+        provider.receive_payment(&subtask, requestor_id, payment);
+
+        if let Some(amount) = payment {
+            self.publish(DomainEvent::Payment {
+                requestor_id,
+                provider_id,
+                subtask_id: *subtask.id(),
+                amount,
+            });
+        }
+    }
+
+    fn handle_submitted(
+        &mut self,
+        subtask: SubTask,
+        requestor_id: Id,
+        provider_id: Id,
+        amount: f64,
+    ) {
+        let settlement = self.settlement.as_mut().expect("settlement not found");
+        let batch_window = settlement.model().batch_window;
+        let opened = settlement.submit(
+            requestor_id,
+            Payment {
+                subtask,
+                provider_id,
+                amount,
+            },
+        );
+
+        if batch_window == 0.0 {
+            self.handle_batch_closed(requestor_id);
+        } else if opened {
+            self.engine
+                .schedule(T::from_secs(batch_window), Event::BatchClosed(requestor_id));
+        }
+    }
+
+    fn handle_batch_closed(&mut self, requestor_id: Id) {
+        let settlement = self.settlement.as_mut().expect("settlement not found");
+        let delay = settlement.model().delay;
+        let (payments, fee) = settlement.close(requestor_id);
+
+        self.requestors
+            .get_mut(&requestor_id)
+            .expect("requestor not found")
+            .pay_transaction_fee(fee);
+
+        // the providers learn of the transaction once it has gone through
+        for payment in payments {
+            self.send_after(
+                delay,
+                Message::Payment,
+                Event::PaymentDelivered(
+                    payment.subtask,
+                    requestor_id,
+                    payment.provider_id,
+                    Some(payment.amount),
+                ),
+            );
+        }
+    }
+
+    fn handle_cancelled(&mut self, subtask: SubTask, requestor_id: Id, provider_id: Id) {
+        let provider = match self.providers.get_mut(&provider_id) {
+            Some(provider) => provider,
+            None => self
+                .departed_providers
+                .get_mut(&provider_id)
+                .expect("provider not found"),
+        };
+
+        provider.receive_cancellation(&subtask, requestor_id);
+    }
+
+    fn handle_provider_arrival(&mut self, source_id: usize) {
+        let source = &self.provider_sources[source_id];
+        let provider = source.sample_provider(&mut self.ids, &mut self.rng);
+        let lifetime = source.sample_lifetime(&mut self.rng);
+        let provider_id = *provider.id();
+
+        // the newcomer benchmarks itself with every requestor in the market
+        let usage_factor = provider.send_benchmark();
+        for requestor in self.requestors.values_mut() {
+            requestor.receive_benchmark(provider_id, usage_factor);
+        }
+
+        self.push_provider(provider);
+
+        if let Some(lifetime) = lifetime {
+            self.schedule_provider_departure(provider_id, lifetime);
+        }
 
+        self.schedule_provider_arrival(source_id);
         self.schedule_advertise();
     }
 
+    fn handle_provider_departure(&mut self, provider_id: Id) {
+        let mut provider = match self.providers.remove(&provider_id) {
+            Some(provider) => provider,
+            None => return,
+        };
+
+        debug!("W:removing {}", provider);
+
+        self.market_view.provider_left(provider_id);
+        let abandoned = provider.leave(&mut self.engine);
+        self.departed_providers.insert(provider_id, provider);
+
+        for &(subtask, requestor_id) in &abandoned {
+            self.requestors
+                .get_mut(&requestor_id)
+                .expect("requestor not found")
+                .notify_provider_left(&mut self.engine, &subtask, provider_id);
+
+            self.publish_requestor_events(requestor_id);
+        }
+
+        if !abandoned.is_empty() {
+            self.schedule_advertise();
+        }
+    }
+
+    fn handle_requestor_arrival(&mut self, source_id: usize) {
+        let (source, defence_mechanism_type) = &self.requestor_sources[source_id];
+        let mut requestor =
+            source.sample_requestor(&mut self.ids, &mut self.rng, *defence_mechanism_type);
+        let lifetime = source.sample_lifetime(&mut self.rng);
+        let requestor_id = *requestor.id();
+
+        // the newcomer benchmarks every provider in the market
+        for (&id, provider) in &self.providers {
+            requestor.receive_benchmark(id, provider.send_benchmark());
+        }
+
+        requestor.advertise(&mut self.engine, &mut self.ids, &mut self.rng);
+        self.push_requestor(requestor);
+
+        if let Some(lifetime) = lifetime {
+            self.schedule_requestor_departure(requestor_id, lifetime);
+        }
+
+        self.schedule_requestor_arrival(source_id);
+    }
+
+    fn handle_requestor_departure(&mut self, requestor_id: Id) {
+        if self.requestors.contains_key(&requestor_id) {
+            debug!("W:R{} leaving the market", requestor_id);
+
+            self.departed_requestors.insert(requestor_id);
+        }
+    }
+
+    fn handle_gossip(&mut self) {
+        if let Some(interval) = self.market_view.gossip_interval() {
+            self.market_view.gossip(&mut self.rng);
+            self.engine.schedule(T::from_secs(interval), Event::Gossip);
+        }
+    }
+
+    fn handle_sample(&mut self) {
+        let interval = match self.sampling {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        let mut sample = Sample::new(self.engine.now().as_secs());
+
+        sample.set_prices(
+            self.providers
+                .values()
+                .map(|provider| provider.price())
+                .collect(),
+        );
+
+        if !self.providers.is_empty() {
+            let idle = self
+                .providers
+                .values()
+                .filter(|provider| provider.is_idle())
+                .count();
+            sample.idle_providers = idle as f64 / self.providers.len() as f64;
+        }
+
+        for (id, requestor) in &self.requestors {
+            if requestor.is_waiting() && !self.departed_requestors.contains(id) {
+                sample.waiting_requestors += 1;
+            }
+
+            sample.subtasks_in_flight += requestor.num_subtasks_in_flight();
+        }
+
+        for provider in self
+            .providers
+            .values()
+            .chain(self.departed_providers.values())
+        {
+            sample.add_revenue(provider.behaviour(), provider.revenue());
+        }
+
+        self.samples.push(sample);
+        self.engine.schedule(T::from_secs(interval), Event::Sample);
+    }
+
+    fn handle_checkpoint(&mut self) {
+        let (interval, path) = match &self.checkpoints {
+            Some(checkpoints) => (checkpoints.interval, checkpoints.path.clone()),
+            None => return,
+        };
+
+        // the next checkpoint has to be pending already when saving, so that
+        // the resumed run carries on checkpointing exactly like this one
+        self.engine
+            .schedule(T::from_secs(interval), Event::Checkpoint);
+
+        if let Some(save_checkpoint) = self.save_checkpoint {
+            match save_checkpoint(self, &path) {
+                Ok(()) => debug!("W:checkpoint saved to {}", path.display()),
+                Err(err) => error!("W:could not save checkpoint to {}: {}", path.display(), err),
+            }
+        }
+    }
+
     fn handle(&mut self, event: Event) {
+        // sampling and checkpointing must not change where a run stops
+        if !matches!(event, Event::Sample | Event::Checkpoint) {
+            self.num_events += 1;
+        }
+
         match event {
-            Event::TaskAdvertisement(requestor_id) => self.handle_advertise(requestor_id),
+            Event::TaskAdvertisement(requestor_id, task_id) => {
+                self.handle_advertise(requestor_id, task_id)
+            }
             Event::SubTaskComputed(subtask, requestor_id, provider_id, bid) => {
                 self.handle_compute(subtask, requestor_id, provider_id, bid)
             }
             Event::SubTaskBudgetExceeded(subtask, requestor_id, provider_id) => {
                 self.handle_budget_exceeded(subtask, requestor_id, provider_id)
             }
+            Event::SubTaskDropped(subtask, requestor_id, provider_id) => {
+                self.handle_dropped(subtask, requestor_id, provider_id)
+            }
+            Event::SubTaskOverdue(subtask, requestor_id, provider_id) => {
+                self.handle_overdue(subtask, requestor_id, provider_id)
+            }
+            Event::SubTaskTimedOut(subtask, requestor_id, provider_id) => {
+                self.handle_timed_out(subtask, requestor_id, provider_id)
+            }
+            Event::OffersReceived(requestor_id, task_id, bids) => {
+                self.handle_offers(requestor_id, task_id, bids)
+            }
+            Event::SubTaskDelivered(subtask, requestor_id, provider_id, bid) => {
+                self.handle_delivery(subtask, requestor_id, provider_id, bid)
+            }
+            Event::SubTaskReported(subtask, requestor_id, provider_id, bid, reported_usage) => {
+                self.handle_report(subtask, requestor_id, provider_id, bid, reported_usage)
+            }
+            Event::PaymentDelivered(subtask, requestor_id, provider_id, payment) => {
+                self.handle_payment(subtask, requestor_id, provider_id, payment)
+            }
+            Event::SubTaskCancelled(subtask, requestor_id, provider_id) => {
+                self.handle_cancelled(subtask, requestor_id, provider_id)
+            }
+            Event::PaymentSubmitted(subtask, requestor_id, provider_id, amount) => {
+                self.handle_submitted(subtask, requestor_id, provider_id, amount)
+            }
+            Event::BatchClosed(requestor_id) => self.handle_batch_closed(requestor_id),
+            Event::ProviderArrival(source_id) => self.handle_provider_arrival(source_id),
+            Event::ProviderDeparture(provider_id) => self.handle_provider_departure(provider_id),
+            Event::RequestorArrival(source_id) => self.handle_requestor_arrival(source_id),
+            Event::RequestorDeparture(requestor_id) => {
+                self.handle_requestor_departure(requestor_id)
+            }
+            Event::Gossip => self.handle_gossip(),
+            Event::Sample => self.handle_sample(),
+            Event::Checkpoint => self.handle_checkpoint(),
         }
     }
 
@@ -191,7 +1147,27 @@ where
             }
         }
 
+        // link up the actors present from the start
+        let providers: Vec<Id> = self.providers.keys().cloned().collect();
+        for &requestor_id in self.requestors.keys() {
+            self.market_view
+                .requestor_joined(requestor_id, &providers, &mut self.rng);
+        }
+
+        if let Some(interval) = self.market_view.gossip_interval() {
+            self.engine.schedule(T::from_secs(interval), Event::Gossip);
+        }
+
+        for source_id in 0..self.provider_sources.len() {
+            self.schedule_provider_arrival(source_id);
+        }
+
+        for source_id in 0..self.requestor_sources.len() {
+            self.schedule_requestor_arrival(source_id);
+        }
+
         self.schedule_advertise();
+        self.has_started = true;
 
         debug!("W:simulation started");
     }
@@ -208,16 +1184,728 @@ where
         }
     }
 
+    fn schedule_provider_arrival(&mut self, source_id: usize) {
+        if let Some(after) = self.provider_sources[source_id].sample_arrival(&mut self.rng) {
+            self.engine
+                .schedule(T::from_secs(after), Event::ProviderArrival(source_id));
+        }
+    }
+
+    fn schedule_requestor_arrival(&mut self, source_id: usize) {
+        let (source, _) = &self.requestor_sources[source_id];
+
+        if let Some(after) = source.sample_arrival(&mut self.rng) {
+            self.engine
+                .schedule(T::from_secs(after), Event::RequestorArrival(source_id));
+        }
+    }
+
     fn schedule_advertise(&mut self) {
         // shuffle requestors
-        let mut ids: Vec<Id> = self.requestors.keys().cloned().collect();
+        let mut ids: Vec<Id> = self
+            .requestors
+            .keys()
+            .filter(|id| !self.departed_requestors.contains(id))
+            .cloned()
+            .collect();
         ids.shuffle(&mut self.rng);
 
         for id in ids {
             self.requestors
                 .get_mut(&id)
                 .expect("requestor not found")
-                .advertise(&mut self.engine, &mut self.rng);
+                .advertise(&mut self.engine, &mut self.ids, &mut self.rng);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use gd_engine::Ticks;
+    use rand::SeedableRng;
+    use statrs::assert_almost_eq;
+
+    use crate::provider::{LinearUsageInflationProvider, RegularProvider};
+    use crate::requestor::DefenceMechanismType;
+    use crate::rng::SimRng;
+    use crate::scenario::RequestorBehaviour;
+    use crate::task::{Deadline, Task};
+
+    fn build_world() -> World<SimRng> {
+        build_world_on()
+    }
+
+    fn build_world_on<T: Time>() -> World<SimRng, T> {
+        let mut ids = IdAllocator::new();
+        let mut world = World::new(SimRng::seed_from_u64(42));
+
+        for &dm_type in &[
+            DefenceMechanismType::Redundancy,
+            DefenceMechanismType::CTasks,
+            DefenceMechanismType::LGRola,
+        ] {
+            let mut requestor = Requestor::new(ids.allocate(), 0.001, 0.5, dm_type);
+            let mut task = Task::new(ids.allocate());
+            for _ in 0..10 {
+                task.push_pending(SubTask::new(ids.allocate(), 200.0, 0.001 * 0.5 * 200.0));
+            }
+            requestor.task_queue_mut().push(task);
+            world.push_requestor(requestor);
+        }
+
+        for i in 0..5 {
+            let usage_factor = 0.1 + 0.15 * f64::from(i);
+            world.push_provider(Box::new(RegularProvider::new(
+                ids.allocate(),
+                0.00001,
+                usage_factor,
+            )));
+        }
+        world.push_provider(Box::new(LinearUsageInflationProvider::new(
+            ids.allocate(),
+            0.00001,
+            0.1,
+            100.0,
+        )));
+        world.set_ids(ids);
+
+        world
+    }
+
+    #[test]
+    fn run_to_horizon() {
+        let mut world = build_world();
+        world.run(40000.0);
+
+        // the clock stops at the horizon and no event is lost on the way
+        assert_almost_eq!(world.engine.now(), 40000.0, 1e-6);
+        assert!(!world.engine.is_empty());
+
+        assert!(world.step());
+        assert!(world.engine.now() > 40000.0);
+    }
+
+    #[test]
+    fn ticks_clock() {
+        let mut world: World<SimRng, Ticks> = build_world_on();
+        world.run(40000.0);
+
+        // the same market runs on integer microseconds
+        assert_almost_eq!(world.now(), 40000.0, 1e-6);
+        let (requestors, _) = world.into_stats(0);
+        assert!(requestors
+            .iter()
+            .all(|stats| stats.num_subtasks_computed > 0));
+    }
+
+    fn churn_source() -> ProviderSource {
+        serde_json::from_str(
+            r#"{
+                "provider_count": 0,
+                "min_price": { "fixed": 0.00001 },
+                "usage_factor": { "fixed": 0.5 },
+                "arrival": { "fixed": 1000 },
+                "lifetime": { "fixed": 1500 }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn stop_conditions() {
+        // repeating task queues are never drained
+        let mut world = build_world();
+        assert!(!world.is_workload_done());
+        let reason = world.run_with(
+            1e6,
+            &[StopCondition::WorkloadDone, StopCondition::MaxEvents(100)],
+        );
+        assert_eq!(reason, StopReason::MaxEvents);
+        assert_eq!(world.num_events(), 100);
+        let stopped_at = world.now();
+
+        // samples do not count towards the events
+        let mut world = build_world();
+        world.enable_sampling(10.0);
+        world.run_with(1e6, &[StopCondition::MaxEvents(100)]);
+        assert!(!world.samples().is_empty());
+        assert_almost_eq!(world.now(), stopped_at, 1e-9);
+
+        let mut world = build_world();
+        for requestor in world.requestors.values_mut() {
+            requestor.task_queue_mut().repeating = false;
+        }
+        let reason = world.run_with(1e6, &[StopCondition::WorkloadDone]);
+        assert_eq!(reason, StopReason::WorkloadDone);
+        assert!(world.now() < 1e6);
+        assert!(world.requestors().all(Requestor::is_done));
+
+        let mut world = build_world();
+        let reason = world.run_with(
+            1e6,
+            &[StopCondition::Predicate(Box::new(|world| {
+                world.now() >= 1000.0
+            }))],
+        );
+        assert_eq!(reason, StopReason::Predicate);
+        assert!(world.now() >= 1000.0);
+
+        let mut world = build_world();
+        assert_eq!(world.run_with(1000.0, &[]), StopReason::Horizon);
+    }
+
+    #[test]
+    fn provider_churn() {
+        let mut world = build_world();
+        world.push_provider_source(churn_source());
+        world.run(10000.5);
+
+        // a newcomer every 1000s, each staying for 1500s
+        assert_eq!(world.providers.len(), 6 + 2);
+        assert_eq!(world.departed_providers.len(), 8);
+
+        let (_, providers) = world.into_stats(0);
+        assert_eq!(providers.len(), 6 + 10);
+    }
+
+    #[test]
+    fn requestor_churn() {
+        let source: RequestorSource = serde_json::from_str(
+            r#"{
+                "requestor_count": 0,
+                "max_price": { "fixed": 0.001 },
+                "budget_factor": { "fixed": 0.5 },
+                "subtask_count": { "fixed": 5 },
+                "nominal_usage": { "fixed": 200 },
+                "arrival": { "fixed": 1000 },
+                "lifetime": { "fixed": 5000 }
+            }"#,
+        )
+        .unwrap();
+
+        let mut world = build_world();
+        world.push_requestor_source(source, DefenceMechanismType::Redundancy);
+        world.run(10000.5);
+
+        assert_eq!(world.requestors.len(), 3 + 10);
+        assert_eq!(world.departed_requestors.len(), 5);
+
+        // every newcomer has its task advertised on joining
+        let (requestors, _) = world.into_stats(0);
+        assert!(requestors[3..]
+            .iter()
+            .all(|requestor| requestor.num_tasks_advertised > 0));
+    }
+
+    #[derive(Debug, Default)]
+    struct FirstAssignment(std::rc::Rc<std::cell::Cell<Option<Id>>>);
+
+    impl EngineObserver<Event, f64> for FirstAssignment {
+        fn on_schedule(&mut self, _time: f64, event: &Event) {
+            if let Event::SubTaskComputed(_, _, provider_id, _) = event {
+                if self.0.get().is_none() {
+                    self.0.set(Some(*provider_id));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn provider_leaves_mid_subtask() {
+        let mut ids = IdAllocator::new();
+        let mut world = World::new(SimRng::seed_from_u64(42));
+
+        let mut requestor =
+            Requestor::new(ids.allocate(), 0.001, 1.0, DefenceMechanismType::CTasks);
+        let mut task = Task::new(ids.allocate());
+        task.push_pending(SubTask::new(ids.allocate(), 200.0, 0.001 * 200.0));
+        requestor.task_queue_mut().push(task);
+        world.push_requestor(requestor);
+
+        world.push_provider(Box::new(RegularProvider::new(ids.allocate(), 0.00001, 1.0)));
+
+        let assigned = FirstAssignment::default();
+        let provider_id = assigned.0.clone();
+        world.add_engine_observer(Box::new(assigned));
+
+        while provider_id.get().is_none() {
+            assert!(world.step());
+        }
+
+        let provider_id = provider_id.get().unwrap();
+        world.schedule_provider_departure(provider_id, 0.0);
+        world.run(world.engine.now() + 100.0);
+
+        assert!(world.departed_providers.contains_key(&provider_id));
+
+        let (requestors, providers) = world.into_stats(0);
+        assert_eq!(providers[0].num_subtasks_cancelled, 1);
+        assert_eq!(providers[0].num_subtasks_computed, 0);
+        assert_eq!(requestors[0].num_subtasks_cancelled, 1);
+    }
+
+    #[test]
+    fn provider_crashes() {
+        let mut ids = IdAllocator::new();
+        let mut world: World<SimRng, f64> = World::new(SimRng::seed_from_u64(42));
+
+        let mut requestor =
+            Requestor::new(ids.allocate(), 0.001, 1.0, DefenceMechanismType::CTasks);
+        let mut task = Task::new(ids.allocate());
+        task.push_pending(SubTask::new(ids.allocate(), 200.0, 0.001 * 200.0));
+        requestor.task_queue_mut().push(task);
+        world.push_requestor(requestor);
+
+        // crashes long before finishing any subtask
+        let mut provider = Box::new(RegularProvider::new(ids.allocate(), 0.00001, 1.0));
+        provider.set_failure_rate(1.0);
+        world.push_provider(provider);
+
+        world.run(1e6);
+
+        // each lost subtask is noticed, requeued and handed out again
+        let (requestors, providers) = world.into_stats(0);
+        assert!(providers[0].num_subtasks_lost > 1);
+        assert_eq!(providers[0].num_subtasks_computed, 0);
+        assert!(requestors[0].num_subtasks_cancelled > 1);
+        // the last loss may not have been noticed by the horizon yet
+        assert!(requestors[0].num_subtasks_cancelled <= providers[0].num_subtasks_lost);
+        assert_eq!(requestors[0].num_tasks_computed, 0);
+    }
+
+    #[test]
+    fn subtask_deadline() {
+        let mut ids = IdAllocator::new();
+        let mut world: World<SimRng, f64> = World::new(SimRng::seed_from_u64(42));
+
+        // the provider needs 200s, yet has only 50s
+        let mut requestor =
+            Requestor::new(ids.allocate(), 0.001, 1.0, DefenceMechanismType::CTasks);
+        let mut task = Task::new(ids.allocate());
+        let mut subtask = SubTask::new(ids.allocate(), 200.0, 0.001 * 200.0);
+        subtask.deadline = Some(Deadline::After(50.0));
+        task.push_pending(subtask);
+        requestor.task_queue_mut().push(task);
+        world.push_requestor(requestor);
+
+        world.push_provider(Box::new(RegularProvider::new(ids.allocate(), 0.00001, 1.0)));
+
+        world.run(1e5);
+
+        let (requestors, providers) = world.into_stats(0);
+        assert!(requestors[0].num_subtasks_timed_out > 1);
+        assert_eq!(requestors[0].num_subtasks_computed, 0);
+        assert_eq!(
+            providers[0].num_subtasks_timed_out,
+            requestors[0].num_subtasks_timed_out
+        );
+        assert_eq!(providers[0].num_subtasks_computed, 0);
+    }
+
+    #[test]
+    fn expired_deadline() {
+        let run = |repeating| {
+            let mut ids = IdAllocator::new();
+            let mut world: World<SimRng, f64> = World::new(SimRng::seed_from_u64(42));
+            let collector = Collector::default();
+            let published = collector.0.clone();
+            world.subscribe(Box::new(collector));
+
+            // the provider needs far longer than the time left until the deadline
+            let mut requestor =
+                Requestor::new(ids.allocate(), 0.001, 1.0, DefenceMechanismType::CTasks);
+            let mut task = Task::new(ids.allocate());
+            let mut subtask = SubTask::new(ids.allocate(), 1e5, 0.001 * 1e5);
+            subtask.deadline = Some(Deadline::At(1e4));
+            task.push_pending(subtask);
+            requestor.task_queue_mut().push(task);
+            requestor.task_queue_mut().repeating = repeating;
+            world.push_requestor(requestor);
+
+            world.push_provider(Box::new(RegularProvider::new(ids.allocate(), 0.00001, 1.0)));
+
+            let reason = world.run_with(1e6, &[StopCondition::WorkloadDone]);
+            let assigned = published
+                .borrow()
+                .iter()
+                .filter(|(_, event)| matches!(event, DomainEvent::SubTaskAssigned { .. }))
+                .count();
+
+            (reason, world, assigned)
+        };
+
+        // once the deadline passes, the subtask is given up on, and the task
+        // finishes without having been computed
+        let (reason, world, assigned) = run(false);
+        assert_eq!(reason, StopReason::WorkloadDone);
+        assert!(world.now() < 2e4);
+        assert_eq!(assigned, 1);
+
+        let (requestors, _) = world.into_stats(0);
+        assert_eq!(requestors[0].num_subtasks_timed_out, 1);
+        assert_eq!(requestors[0].num_subtasks_expired, 1);
+        assert_eq!(requestors[0].num_tasks_computed, 0);
+
+        // nor are copies of the task handed out anymore
+        let (_, world, assigned) = run(true);
+        assert_eq!(assigned, 1);
+        assert!(world.requestors().all(Requestor::is_done));
+    }
+
+    #[test]
+    fn withdrawn_in_transit() {
+        let mut ids = IdAllocator::new();
+        let mut world: World<SimRng, f64> = World::new(SimRng::seed_from_u64(42));
+        world.set_latency(serde_json::from_str(r#"{ "subtask": { "fixed": 100 } }"#).unwrap());
+
+        // the deadline passes before the subtask reaches the provider
+        let mut requestor =
+            Requestor::new(ids.allocate(), 0.001, 1.0, DefenceMechanismType::CTasks);
+        let mut task = Task::new(ids.allocate());
+        let mut subtask = SubTask::new(ids.allocate(), 10.0, 0.001 * 10.0);
+        subtask.deadline = Some(Deadline::After(50.0));
+        task.push_pending(subtask);
+        requestor.task_queue_mut().push(task);
+        world.push_requestor(requestor);
+
+        world.push_provider(Box::new(RegularProvider::new(ids.allocate(), 0.00001, 1.0)));
+
+        world.run(1e5);
+
+        // the provider never computes what was withdrawn, and hence is not
+        // left unpaid either
+        let (requestors, providers) = world.into_stats(0);
+        assert!(requestors[0].num_subtasks_timed_out > 1);
+        assert_eq!(requestors[0].num_subtasks_computed, 0);
+        assert_eq!(providers[0].num_subtasks_computed, 0);
+        assert_eq!(providers[0].num_subtasks_unpaid, 0);
+        assert_almost_eq!(providers[0].revenue_lost, 0.0, 1e-9);
+    }
+
+    #[test]
+    fn latency_tail() {
+        let mut ids = IdAllocator::new();
+        let mut world: World<SimRng, f64> = World::new(SimRng::seed_from_u64(42));
+        world.set_latency(
+            serde_json::from_str(r#"{ "subtask": { "exp": 0.001 }, "result": { "exp": 0.001 } }"#)
+                .unwrap(),
+        );
+
+        // the messages take far longer than computing the subtasks
+        let mut requestor =
+            Requestor::new(ids.allocate(), 0.001, 1.0, DefenceMechanismType::CTasks);
+        let mut task = Task::new(ids.allocate());
+        for _ in 0..10 {
+            task.push_pending(SubTask::new(ids.allocate(), 10.0, 0.001 * 10.0));
+        }
+        requestor.task_queue_mut().push(task);
+        world.push_requestor(requestor);
+
+        for _ in 0..5 {
+            world.push_provider(Box::new(RegularProvider::new(ids.allocate(), 0.00001, 1.0)));
+        }
+
+        let log = DispatchLog::default();
+        let dispatched = log.0.clone();
+        world.add_engine_observer(Box::new(log));
+
+        world.run(1e6);
+
+        // none of the providers fails, hence no result is presumed lost on
+        // the way, however long it takes
+        assert!(!dispatched
+            .borrow()
+            .iter()
+            .any(|&(_, kind)| kind == "subtask_overdue"));
+
+        let (requestors, providers) = world.into_stats(0);
+        assert!(requestors[0].num_subtasks_computed > 1000);
+        assert!(providers
+            .iter()
+            .all(|stats| stats.num_subtasks_unpaid == 0 && stats.revenue_lost == 0.0));
+    }
+
+    #[test]
+    fn late_results_written_off() {
+        let mut ids = IdAllocator::new();
+        let mut world: World<SimRng, f64> = World::new(SimRng::seed_from_u64(42));
+        world.set_latency(serde_json::from_str(r#"{ "result": { "fixed": 100 } }"#).unwrap());
+
+        // the provider computes the subtask in 200s, yet its result arrives
+        // after the deadline
+        let mut requestor =
+            Requestor::new(ids.allocate(), 0.001, 1.0, DefenceMechanismType::CTasks);
+        let mut task = Task::new(ids.allocate());
+        let mut subtask = SubTask::new(ids.allocate(), 200.0, 0.001 * 200.0);
+        subtask.deadline = Some(Deadline::After(210.0));
+        task.push_pending(subtask);
+        requestor.task_queue_mut().push(task);
+        world.push_requestor(requestor);
+
+        world.push_provider(Box::new(RegularProvider::new(ids.allocate(), 0.00001, 1.0)));
+
+        world.run(1e5);
+
+        // each result discarded as late is written off, and none is left
+        // outstanding
+        let (requestors, providers) = world.into_stats(0);
+        assert!(requestors[0].num_subtasks_timed_out > 1);
+        assert_eq!(
+            providers[0].num_subtasks_unpaid,
+            requestors[0].num_subtasks_timed_out
+        );
+        assert_eq!(
+            providers[0].num_subtasks_computed,
+            providers[0].num_subtasks_unpaid + requestors[0].num_subtasks_computed
+        );
+        assert!(providers[0].revenue_lost > 0.0);
+        assert_almost_eq!(providers[0].receivables, 0.0, 1e-9);
+    }
+
+    #[test]
+    fn dishonest_requestors() {
+        let run = |behaviour| {
+            let mut world = build_world();
+            for requestor in world.requestors.values_mut() {
+                requestor.set_behaviour(behaviour);
+            }
+            world.run(40000.0);
+
+            let (_, providers) = world.into_stats(0);
+            let revenue: f64 = providers.iter().map(|stats| stats.revenue).sum();
+            let lost: f64 = providers.iter().map(|stats| stats.revenue_lost).sum();
+            (revenue, lost)
+        };
+
+        let (revenue, lost) = run(RequestorBehaviour::Honest);
+        assert!(revenue > 0.0);
+        assert_almost_eq!(lost, 0.0, 1e-9);
+
+        // the same seed yields the same assignments, hence the same amounts due
+        let (revenue, lost) = run(RequestorBehaviour::PayFraction(0.25));
+        assert_almost_eq!(revenue, lost / 3.0, 1e-9);
+
+        let (revenue, lost) = run(RequestorBehaviour::NeverPay);
+        assert_almost_eq!(revenue, 0.0, 1e-9);
+        assert!(lost > 0.0);
+
+        let (revenue, lost) = run(RequestorBehaviour::CancelAfterCompute);
+        assert_almost_eq!(revenue, 0.0, 1e-9);
+        assert!(lost > 0.0);
+    }
+
+    #[test]
+    fn payment_settlement() {
+        let mut world = build_world();
+        world.run(40000.0);
+        let (_, providers) = world.into_stats(0);
+        let expected: f64 = providers.iter().map(|stats| stats.revenue).sum();
+
+        let mut world = build_world();
+        world.set_settlement(SettlementModel {
+            delay: 600.0,
+            batch_window: 3600.0,
+            fixed_fee: 0.01,
+            proportional_fee: 0.05,
+        });
+        world.run(40000.0);
+        let (requestors, providers) = world.into_stats(0);
+
+        // whatever has not arrived yet is still owed
+        let revenue: f64 = providers.iter().map(|stats| stats.revenue).sum();
+        let receivables: f64 = providers.iter().map(|stats| stats.receivables).sum();
+        assert!(revenue < expected);
+        assert_almost_eq!(revenue + receivables, expected, 1e-9);
+
+        // payments are batched, and each batch costs a fee
+        let transactions: usize = requestors.iter().map(|stats| stats.num_transactions).sum();
+        let computed: usize = requestors
+            .iter()
+            .map(|stats| stats.num_subtasks_computed)
+            .sum();
+        let fees: f64 = requestors.iter().map(|stats| stats.fees_paid).sum();
+        assert!(transactions > 0 && transactions < computed);
+        assert!(fees > 0.01 * transactions as f64);
+        assert!(fees <= 0.01 * transactions as f64 + 0.05 * expected);
+    }
+
+    #[test]
+    fn sample_market() {
+        let mut world = build_world();
+        world.enable_sampling(1000.0);
+        world.run(40000.0);
+
+        let samples = world.samples();
+        assert!(samples.len() > 40);
+        assert_eq!(samples[0].time, 0.0);
+        assert!(samples.windows(2).all(|w| w[1].time - w[0].time == 1000.0));
+
+        // revenue only ever accumulates
+        let revenue = |sample: &Sample| {
+            sample.revenue_regular
+                + sample.revenue_linear_usage_inflation
+                + sample.revenue_undercut_budget
+        };
+        assert!(samples.windows(2).all(|w| revenue(&w[1]) >= revenue(&w[0])));
+        assert!(revenue(samples.last().unwrap()) > 0.0);
+        assert!(samples
+            .iter()
+            .all(|sample| sample.idle_providers >= 0.0 && sample.idle_providers <= 1.0));
+    }
+
+    #[derive(Debug, Default)]
+    struct Collector(std::rc::Rc<std::cell::RefCell<Vec<(f64, DomainEvent)>>>);
+
+    impl Subscriber for Collector {
+        fn notify(&mut self, time: f64, event: &DomainEvent) {
+            self.0.borrow_mut().push((time, event.clone()));
+        }
+    }
+
+    #[test]
+    fn domain_events() {
+        let mut world = build_world();
+        let collector = Collector::default();
+        let published = collector.0.clone();
+        world.subscribe(Box::new(collector));
+        world.run(40000.0);
+
+        let published = published.borrow();
+        assert!(published.windows(2).all(|w| w[0].0 <= w[1].0));
+
+        let count = |kind: fn(&DomainEvent) -> bool| {
+            published.iter().filter(|(_, event)| kind(event)).count()
+        };
+        let assigned = count(|event| matches!(event, DomainEvent::SubTaskAssigned { .. }));
+        let reported = count(|event| matches!(event, DomainEvent::UsageReported { .. }));
+        let paid = count(|event| matches!(event, DomainEvent::Payment { .. }));
+
+        assert!(count(|event| matches!(event, DomainEvent::OfferSent { .. })) >= assigned);
+        assert!(assigned >= reported && reported > 0);
+        assert_eq!(paid, reported);
+        assert!(count(|event| matches!(event, DomainEvent::Verified { .. })) >= reported);
+        assert!(count(|event| matches!(event, DomainEvent::RatingUpdated { .. })) > 0);
+
+        // each usage report is followed by the verification of the subtask
+        for (i, (_, event)) in published.iter().enumerate() {
+            if let DomainEvent::UsageReported { subtask_id, .. } = event {
+                assert!(published[i..].iter().any(|(_, event)| match event {
+                    DomainEvent::Verified { subtask_id: id, .. } => id == subtask_id,
+                    _ => false,
+                }));
+            }
+        }
+    }
+
+    type Dispatched = std::rc::Rc<std::cell::RefCell<Vec<(f64, &'static str)>>>;
+
+    #[derive(Debug, Default)]
+    struct DispatchLog(Dispatched);
+
+    impl EngineObserver<Event, f64> for DispatchLog {
+        fn on_dispatch(&mut self, time: f64, event: &Event) {
+            self.0.borrow_mut().push((time, event.kind()));
         }
     }
+
+    #[test]
+    fn inline_dispatch() {
+        let mut world = build_world();
+        world.enable_profiling();
+        let log = DispatchLog::default();
+        let dispatched = log.0.clone();
+        world.add_engine_observer(Box::new(log));
+
+        // messages are delivered instantly, bypassing the queue
+        world.run(40000.0);
+
+        let num_events = world.num_events();
+        assert_eq!(dispatched.borrow().len() as u64, num_events);
+        assert_eq!(world.profile().unwrap().num_events(), num_events);
+    }
+
+    #[test]
+    fn message_latency() {
+        let mut ids = IdAllocator::new();
+        let mut world = World::new(SimRng::seed_from_u64(42));
+        world.set_latency(
+            serde_json::from_str(
+                r#"{
+                    "offer": { "fixed": 10 },
+                    "subtask": { "fixed": 5 },
+                    "result": { "fixed": 3 },
+                    "payment": { "fixed": 7 }
+                }"#,
+            )
+            .unwrap(),
+        );
+
+        let mut requestor =
+            Requestor::new(ids.allocate(), 0.001, 1.0, DefenceMechanismType::CTasks);
+        let mut task = Task::new(ids.allocate());
+        task.push_pending(SubTask::new(ids.allocate(), 200.0, 0.001 * 200.0));
+        requestor.task_queue_mut().push(task);
+        world.push_requestor(requestor);
+
+        world.push_provider(Box::new(RegularProvider::new(ids.allocate(), 0.00001, 1.0)));
+
+        let log = DispatchLog::default();
+        let dispatched = log.0.clone();
+        world.add_engine_observer(Box::new(log));
+
+        while world.step() {
+            if dispatched.borrow().len() == 6 {
+                break;
+            }
+        }
+
+        let dispatched = dispatched.borrow();
+        let kinds: Vec<&str> = dispatched.iter().map(|&(_, kind)| kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "task_advertisement",
+                "offers_received",
+                "subtask_delivered",
+                "subtask_computed",
+                "subtask_reported",
+                "payment_delivered",
+            ]
+        );
+
+        let delays: Vec<f64> = dispatched.windows(2).map(|w| w[1].0 - w[0].0).collect();
+        assert_almost_eq!(delays[0], 10.0, 1e-9);
+        assert_almost_eq!(delays[1], 5.0, 1e-9);
+        assert_almost_eq!(delays[3], 3.0, 1e-9);
+        assert_almost_eq!(delays[4], 7.0, 1e-9);
+    }
+
+    #[test]
+    fn resume_from_checkpoint() {
+        let path =
+            std::env::temp_dir().join(format!("gd-world-checkpoint-{}.bin", std::process::id()));
+
+        let mut world = build_world();
+        world.enable_checkpoints(30000.0, &path);
+        world.run(100000.0);
+        let num_events = world.num_events();
+        let expected = format!("{:?}", world.into_stats(0));
+
+        // the last checkpoint was taken at 90000; enabling checkpoints again
+        // must not schedule a second chain of them
+        let mut world: World<SimRng> = World::load_checkpoint(&path).unwrap();
+        assert_almost_eq!(world.engine.now(), 90000.0, 1e-6);
+        let num_pending = world.engine.len();
+        world.enable_checkpoints(30000.0, &path);
+        assert_eq!(world.engine.len(), num_pending);
+        world.run(100000.0);
+        assert_eq!(world.num_events(), num_events);
+        let actual = format!("{:?}", world.into_stats(0));
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(expected, actual);
+    }
 }